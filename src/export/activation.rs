@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+/// # Activation function of the hidden layers
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Activation {
+    Relu,
+    LeakyRelu,
+    Tanh,
+    Elu,
+    Gelu,
+}
@@ -0,0 +1,180 @@
+use crate::export::activation::Activation;
+use crate::export::portable::{LayerNormParams, PortableModel};
+
+/// # MLP policy
+///
+/// Inference only actor evaluating a portable model with plain f32 arithmetic
+/// instead of libtorch
+pub struct MlpPolicy {
+    model: PortableModel,
+}
+
+impl MlpPolicy {
+    pub fn new(model: PortableModel) -> Self {
+        Self { model }
+    }
+
+    pub fn load(path: &str) -> Self {
+        Self::new(PortableModel::load(path))
+    }
+
+    // network outputs for a single observation
+    pub fn forward(&self, obs: &[f32]) -> Vec<f32> {
+        let metadata = &self.model.metadata;
+        assert_eq!(
+            obs.len(),
+            metadata.observation_space,
+            "The observation doesn't match the model"
+        );
+        let normalization = &metadata.normalization;
+        let mut xs = obs
+            .iter()
+            .zip(&normalization.mean)
+            .zip(&normalization.std)
+            .map(|((x, mean), std)| (x - mean) / std)
+            .collect::<Vec<f32>>();
+        for layer in &self.model.layers {
+            let mut ys = layer
+                .weight
+                .chunks(layer.inputs)
+                .zip(&layer.bias)
+                .map(|(row, bias)| row.iter().zip(&xs).map(|(w, x)| w * x).sum::<f32>() + bias)
+                .collect::<Vec<f32>>();
+            if let Some(norm) = &layer.norm {
+                layer_norm(&mut ys, norm);
+            }
+            if layer.activation {
+                ys.iter_mut()
+                    .for_each(|y| *y = activate(*y, metadata.activation));
+            }
+            xs = ys;
+        }
+        xs
+    }
+
+    // index of the highest output, the first one on ties as in `greedy_action`
+    pub fn act(&self, obs: &[f32]) -> usize {
+        self.forward(obs)
+            .iter()
+            .enumerate()
+            .fold(
+                (0, f32::MIN),
+                |best, (i, &y)| if y > best.1 { (i, y) } else { best },
+            )
+            .0
+    }
+}
+
+fn layer_norm(xs: &mut [f32], norm: &LayerNormParams) {
+    let n = xs.len() as f32;
+    let mean = xs.iter().sum::<f32>() / n;
+    let var = xs.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / n;
+    let std = (var + norm.eps).sqrt();
+    for ((x, weight), bias) in xs.iter_mut().zip(&norm.weight).zip(&norm.bias) {
+        *x = (*x - mean) / std * weight + bias;
+    }
+}
+
+fn activate(x: f32, activation: Activation) -> f32 {
+    match activation {
+        Activation::Relu => x.max(0.),
+        Activation::LeakyRelu => {
+            if x > 0. {
+                x
+            } else {
+                0.01 * x
+            }
+        }
+        Activation::Tanh => x.tanh(),
+        Activation::Elu => {
+            if x > 0. {
+                x
+            } else {
+                x.exp_m1()
+            }
+        }
+        Activation::Gelu => 0.5 * x * (1. + erf(x / 2f32.sqrt())),
+    }
+}
+
+// Abramowitz and Stegun 7.1.26, absolute error below 1.5e-7
+fn erf(x: f32) -> f32 {
+    let t = 1. / (1. + 0.3275911 * x.abs());
+    let poly = t
+        * (0.254_829_6
+            + t * (-0.284_496_72 + t * (1.421_413_8 + t * (-1.453_152_1 + t * 1.061_405_4))));
+    (1. - poly * (-x * x).exp()).copysign(x)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::export::portable::{DenseLayer, ModelMetadata, Normalization, FORMAT_VERSION};
+
+    // relu layer followed by a layer normalized output layer
+    pub(crate) fn model() -> PortableModel {
+        PortableModel {
+            format_version: FORMAT_VERSION,
+            metadata: ModelMetadata {
+                observation_space: 2,
+                action_space: 2,
+                observation_layout: vec!["x".to_string(), "y".to_string()],
+                action_layout: vec!["left".to_string(), "right".to_string()],
+                normalization: Normalization {
+                    mean: vec![1., 0.],
+                    std: vec![2., 1.],
+                },
+                activation: Activation::Relu,
+            },
+            layers: vec![
+                DenseLayer {
+                    inputs: 2,
+                    outputs: 2,
+                    weight: vec![1., 0., 0., -1.],
+                    bias: vec![0., 0.5],
+                    norm: None,
+                    activation: true,
+                },
+                DenseLayer {
+                    inputs: 2,
+                    outputs: 2,
+                    weight: vec![2., 0., 0., 1.],
+                    bias: vec![0., 0.],
+                    norm: Some(LayerNormParams {
+                        weight: vec![1., 1.],
+                        bias: vec![0., 0.],
+                        eps: 0.,
+                    }),
+                    activation: false,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn forward() {
+        let policy = MlpPolicy::new(model());
+        // normalized [1, 2], hidden relu([1, -1.5]) = [1, 0], output norm([2, 0])
+        assert_eq!(policy.forward(&[3., 2.]), [1., -1.]);
+        assert_eq!(policy.act(&[3., 2.]), 0);
+        // normalized [-1, -1], hidden relu([-1, 1.5]) = [0, 1.5], output norm([0, 1.5])
+        assert_eq!(policy.forward(&[-1., -1.]), [-1., 1.]);
+        assert_eq!(policy.act(&[-1., -1.]), 1);
+    }
+
+    #[test]
+    fn activations() {
+        assert_eq!(activate(-2., Activation::Relu), 0.);
+        assert_eq!(activate(-2., Activation::LeakyRelu), -0.02);
+        assert_eq!(activate(2., Activation::Elu), 2.);
+        assert!((activate(-1., Activation::Elu) - (-1f32).exp_m1()).abs() < 1e-6);
+        assert!((activate(1., Activation::Gelu) - 0.841_344_7).abs() < 1e-6);
+        assert!(activate(-10., Activation::Gelu).abs() < 1e-6);
+    }
+
+    #[test]
+    #[should_panic(expected = "The observation doesn't match the model")]
+    fn wrong_observation() {
+        MlpPolicy::new(model()).forward(&[1.]);
+    }
+}
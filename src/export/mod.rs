@@ -0,0 +1,6 @@
+pub mod activation;
+pub mod mlp;
+pub mod onnx;
+pub mod portable;
+#[cfg(feature = "torch")]
+mod var_store;
@@ -0,0 +1,308 @@
+use crate::export::activation::Activation;
+use crate::export::portable::PortableModel;
+use std::fs::write;
+
+const IR_VERSION: i64 = 8;
+// LayerNormalization needs opset 17
+const OPSET_VERSION: i64 = 17;
+const FLOAT: i64 = 1;
+const INT64: i64 = 7;
+
+/// # Protobuf message
+///
+/// Minimal writer of the protobuf wire format, enough for the ONNX messages used by the export
+#[derive(Default)]
+struct Message(Vec<u8>);
+
+impl Message {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push((value as u8 & 0x7f) | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    fn key(&mut self, field: u64, wire_type: u64) {
+        self.varint(field << 3 | wire_type);
+    }
+
+    fn int(&mut self, field: u64, value: i64) -> &mut Self {
+        self.key(field, 0);
+        self.varint(value as u64);
+        self
+    }
+
+    fn float(&mut self, field: u64, value: f32) -> &mut Self {
+        self.key(field, 5);
+        self.0.extend(value.to_le_bytes());
+        self
+    }
+
+    fn bytes(&mut self, field: u64, value: &[u8]) -> &mut Self {
+        self.key(field, 2);
+        self.varint(value.len() as u64);
+        self.0.extend(value);
+        self
+    }
+
+    fn string(&mut self, field: u64, value: &str) -> &mut Self {
+        self.bytes(field, value.as_bytes())
+    }
+
+    fn message(&mut self, field: u64, value: &Message) -> &mut Self {
+        self.bytes(field, &value.0)
+    }
+}
+
+fn int_attribute(name: &str, value: i64) -> Message {
+    let mut attribute = Message::default();
+    attribute.string(1, name).int(3, value).int(20, 2);
+    attribute
+}
+
+fn float_attribute(name: &str, value: f32) -> Message {
+    let mut attribute = Message::default();
+    attribute.string(1, name).float(2, value).int(20, 1);
+    attribute
+}
+
+// tensor of shape [batch, features]
+fn value_info(name: &str, elem_type: i64, features: Option<usize>) -> Message {
+    let mut shape = Message::default();
+    let mut batch = Message::default();
+    batch.string(2, "batch");
+    shape.message(1, &batch);
+    if let Some(features) = features {
+        let mut dim = Message::default();
+        dim.int(1, features as i64);
+        shape.message(1, &dim);
+    }
+    let mut tensor_type = Message::default();
+    tensor_type.int(1, elem_type).message(2, &shape);
+    let mut type_proto = Message::default();
+    type_proto.message(1, &tensor_type);
+    let mut info = Message::default();
+    info.string(1, name).message(2, &type_proto);
+    info
+}
+
+/// # ONNX graph
+///
+/// - `nodes`: operations in topological order
+/// - `initializers`: constant tensors (weights, biases and normalization stats)
+#[derive(Default)]
+struct Graph {
+    nodes: Vec<Message>,
+    initializers: Vec<Message>,
+}
+
+impl Graph {
+    fn initializer(&mut self, name: &str, dims: &[usize], values: &[f32]) -> String {
+        let mut tensor = Message::default();
+        for &dim in dims {
+            tensor.int(1, dim as i64);
+        }
+        let raw = values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<u8>>();
+        tensor.int(2, FLOAT).string(8, name).bytes(9, &raw);
+        self.initializers.push(tensor);
+        name.to_string()
+    }
+
+    fn node_to(&mut self, op: &str, inputs: &[&str], output: &str, attributes: &[Message]) {
+        let mut node = Message::default();
+        for input in inputs {
+            node.string(1, input);
+        }
+        node.string(2, output)
+            .string(3, &format!("{}_{}", op, self.nodes.len()))
+            .string(4, op);
+        for attribute in attributes {
+            node.message(5, attribute);
+        }
+        self.nodes.push(node);
+    }
+
+    // node with a generated output name
+    fn node(&mut self, op: &str, inputs: &[&str], attributes: &[Message]) -> String {
+        let output = format!("{}_{}_out", op, self.nodes.len());
+        self.node_to(op, inputs, &output, attributes);
+        output
+    }
+
+    fn activation(&mut self, xs: &str, activation: Activation) -> String {
+        match activation {
+            Activation::Relu => self.node("Relu", &[xs], &[]),
+            Activation::LeakyRelu => {
+                self.node("LeakyRelu", &[xs], &[float_attribute("alpha", 0.01)])
+            }
+            Activation::Tanh => self.node("Tanh", &[xs], &[]),
+            Activation::Elu => self.node("Elu", &[xs], &[float_attribute("alpha", 1.)]),
+            // Gelu is an operator only from opset 20: x * 0.5 * (1 + erf(x / sqrt(2)))
+            Activation::Gelu => {
+                let sqrt2 = format!("gelu_sqrt2_{}", self.nodes.len());
+                self.initializer(&sqrt2, &[1], &[2f32.sqrt()]);
+                let half = format!("gelu_half_{}", self.nodes.len());
+                self.initializer(&half, &[1], &[0.5]);
+                let one = format!("gelu_one_{}", self.nodes.len());
+                self.initializer(&one, &[1], &[1.]);
+                let scaled = self.node("Div", &[xs, &sqrt2], &[]);
+                let erf = self.node("Erf", &[&scaled], &[]);
+                let shifted = self.node("Add", &[&erf, &one], &[]);
+                let halved = self.node("Mul", &[&shifted, &half], &[]);
+                self.node("Mul", &[xs, &halved], &[])
+            }
+        }
+    }
+}
+
+// graph from `obs` [batch, observations] to `actions` [batch, actions] and their argmax `action` [batch],
+// the metadata is stored as JSON in the `metadata` property
+pub fn save_onnx(model: &PortableModel, path: &str) {
+    let metadata = &model.metadata;
+    let mut graph = Graph::default();
+    let n = metadata.observation_space;
+    let mean = graph.initializer("obs_mean", &[n], &metadata.normalization.mean);
+    let std = graph.initializer("obs_std", &[n], &metadata.normalization.std);
+    let centered = graph.node("Sub", &["obs", &mean], &[]);
+    let mut xs = graph.node("Div", &[&centered, &std], &[]);
+    for (i, layer) in model.layers.iter().enumerate() {
+        let weight = graph.initializer(
+            &format!("layer{}_weight", i),
+            &[layer.outputs, layer.inputs],
+            &layer.weight,
+        );
+        let bias = graph.initializer(&format!("layer{}_bias", i), &[layer.outputs], &layer.bias);
+        xs = graph.node(
+            "Gemm",
+            &[&xs, &weight, &bias],
+            &[int_attribute("transB", 1)],
+        );
+        if let Some(norm) = &layer.norm {
+            let scale = graph.initializer(
+                &format!("layer{}_norm_weight", i),
+                &[layer.outputs],
+                &norm.weight,
+            );
+            let shift = graph.initializer(
+                &format!("layer{}_norm_bias", i),
+                &[layer.outputs],
+                &norm.bias,
+            );
+            xs = graph.node(
+                "LayerNormalization",
+                &[&xs, &scale, &shift],
+                &[
+                    int_attribute("axis", -1),
+                    float_attribute("epsilon", norm.eps),
+                ],
+            );
+        }
+        if layer.activation {
+            xs = graph.activation(&xs, metadata.activation);
+        }
+    }
+    graph.node_to("Identity", &[&xs], "actions", &[]);
+    graph.node_to(
+        "ArgMax",
+        &["actions"],
+        "action",
+        &[int_attribute("axis", 1), int_attribute("keepdims", 0)],
+    );
+
+    let mut graph_proto = Message::default();
+    for node in &graph.nodes {
+        graph_proto.message(1, node);
+    }
+    graph_proto.string(2, "actor");
+    for initializer in &graph.initializers {
+        graph_proto.message(5, initializer);
+    }
+    graph_proto
+        .message(11, &value_info("obs", FLOAT, Some(n)))
+        .message(
+            12,
+            &value_info("actions", FLOAT, Some(metadata.action_space)),
+        )
+        .message(12, &value_info("action", INT64, None));
+
+    let mut opset = Message::default();
+    opset.string(1, "").int(2, OPSET_VERSION);
+    let mut metadata_prop = Message::default();
+    metadata_prop
+        .string(1, "metadata")
+        .string(2, &serde_json::to_string(metadata).unwrap());
+    let mut model_proto = Message::default();
+    model_proto
+        .int(1, IR_VERSION)
+        .string(2, env!("CARGO_PKG_NAME"))
+        .message(7, &graph_proto)
+        .message(8, &opset)
+        .message(14, &metadata_prop);
+    write(path, model_proto.0).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::mlp::tests::model;
+    use std::fs::read;
+
+    // nodes with the given operator, found through their encoded `op_type` field
+    fn count_op(bytes: &[u8], op: &str) -> usize {
+        let mut field = vec![0x22, op.len() as u8];
+        field.extend(op.as_bytes());
+        bytes
+            .windows(field.len())
+            .filter(|window| *window == field)
+            .count()
+    }
+
+    fn contains(bytes: &[u8], value: &[u8]) -> bool {
+        bytes.windows(value.len()).any(|window| window == value)
+    }
+
+    #[test]
+    fn same_layers_as_the_mlp() {
+        let model = model();
+        let path = std::env::temp_dir().join("onnx_same_layers_as_the_mlp.onnx");
+        save_onnx(&model, path.to_str().unwrap());
+        let bytes = read(&path).unwrap();
+
+        // normalization, a dense layer per portable layer, then the outputs
+        assert_eq!(count_op(&bytes, "Sub"), 1);
+        assert_eq!(count_op(&bytes, "Div"), 1);
+        assert_eq!(count_op(&bytes, "Gemm"), model.layers.len());
+        assert_eq!(
+            count_op(&bytes, "LayerNormalization"),
+            model.layers.iter().filter(|l| l.norm.is_some()).count()
+        );
+        assert_eq!(
+            count_op(&bytes, "Relu"),
+            model.layers.iter().filter(|l| l.activation).count()
+        );
+        assert_eq!(count_op(&bytes, "Identity"), 1);
+        assert_eq!(count_op(&bytes, "ArgMax"), 1);
+
+        // the weights are stored as they are evaluated by the mlp
+        for layer in &model.layers {
+            let raw = layer
+                .weight
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect::<Vec<u8>>();
+            assert!(contains(&bytes, &raw));
+        }
+        for name in [
+            "obs_mean",
+            "obs_std",
+            "layer1_norm_weight",
+            "layer1_norm_bias",
+        ] {
+            assert!(contains(&bytes, name.as_bytes()));
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::export::activation::Activation;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+
+pub const FORMAT_VERSION: u32 = 1;
+
+/// # Layer normalization parameters
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LayerNormParams {
+    pub weight: Vec<f32>,
+    pub bias: Vec<f32>,
+    pub eps: f32,
+}
+
+/// # Dense layer
+///
+/// - `weight`: row major matrix of shape [outputs, inputs]
+/// - `bias`: one value per output
+/// - `norm`: layer norm applied before the activation, batch norm is folded into the weights
+/// - `activation`: whether the activation of the model follows the layer
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DenseLayer {
+    pub inputs: usize,
+    pub outputs: usize,
+    pub weight: Vec<f32>,
+    pub bias: Vec<f32>,
+    pub norm: Option<LayerNormParams>,
+    pub activation: bool,
+}
+
+/// # Observation normalization
+///
+/// The network gets `(obs - mean) / std`, the training doesn't normalize the observations
+/// so the exported models have zero mean and unit std
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Normalization {
+    pub mean: Vec<f32>,
+    pub std: Vec<f32>,
+}
+
+/// # Model metadata
+///
+/// - `observation_layout`: name of every observation feature in input order
+/// - `action_layout`: name of every output, the robot performs the argmax
+/// - `normalization`: normalization of the observations
+/// - `activation`: activation of the hidden layers
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ModelMetadata {
+    pub observation_space: usize,
+    pub action_space: usize,
+    pub observation_layout: Vec<String>,
+    pub action_layout: Vec<String>,
+    pub normalization: Normalization,
+    pub activation: Activation,
+}
+
+/// # Portable model
+///
+/// Framework free description of a feed forward actor, saved as JSON
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PortableModel {
+    pub format_version: u32,
+    pub metadata: ModelMetadata,
+    pub layers: Vec<DenseLayer>,
+}
+
+impl PortableModel {
+    pub fn save(&self, path: &str) {
+        serde_json::to_writer(File::create(path).unwrap(), self).unwrap();
+    }
+
+    pub fn load(path: &str) -> Self {
+        let model: Self = serde_json::from_reader(File::open(path).unwrap()).unwrap();
+        assert_eq!(
+            model.format_version, FORMAT_VERSION,
+            "Unsupported portable model version"
+        );
+        model
+    }
+}
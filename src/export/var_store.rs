@@ -0,0 +1,113 @@
+use crate::export::portable::{
+    DenseLayer, LayerNormParams, ModelMetadata, Normalization, PortableModel, FORMAT_VERSION,
+};
+use crate::model::{Activation, NetworkSpec, Norm};
+use crate::utils::functions::{action_name, observation_layout};
+use std::collections::HashMap;
+use tch::nn::VarStore;
+use tch::{Device, Kind, Tensor};
+
+// epsilon used by the tch batch and layer norms
+const NORM_EPS: f32 = 1e-5;
+
+impl ModelMetadata {
+    // layouts of the current configuration
+    pub fn new(observation_space: usize, action_space: usize, activation: Activation) -> Self {
+        Self {
+            observation_space,
+            action_space,
+            observation_layout: observation_layout(),
+            action_layout: (0..action_space as i64).map(action_name).collect(),
+            normalization: Normalization {
+                mean: vec![0.; observation_space],
+                std: vec![1.; observation_space],
+            },
+            activation,
+        }
+    }
+}
+
+impl PortableModel {
+    // layers of an actor built by `create_network` with the given spec
+    pub fn from_var_store(vs: &VarStore, spec: &NetworkSpec, metadata: ModelMetadata) -> Self {
+        assert!(
+            spec.encoder.is_none(),
+            "Actors with a convolutional encoder can't be exported"
+        );
+        let variables = vs.variables();
+        let names = (0..spec.layers.len())
+            .map(|i| {
+                if i == 0 {
+                    "in".to_string()
+                } else {
+                    format!("hd{}", i - 1)
+                }
+            })
+            .chain(["out".to_string()]);
+        let layers = names
+            .map(|name| {
+                let hidden = name != "out";
+                let norm = if hidden { spec.norm } else { Norm::None };
+                dense_layer(&variables, &name, norm, hidden)
+            })
+            .collect();
+        Self {
+            format_version: FORMAT_VERSION,
+            metadata,
+            layers,
+        }
+    }
+}
+
+fn dense_layer(
+    variables: &HashMap<String, Tensor>,
+    name: &str,
+    norm: Norm,
+    activation: bool,
+) -> DenseLayer {
+    let get = |suffix: &str| values(&variables[&format!("{}.{}", name, suffix)]);
+    let get_norm = |suffix: &str| values(&variables[&format!("{}_norm.{}", name, suffix)]);
+    let mut weight = get("weight");
+    let mut bias = get("bias");
+    let outputs = bias.len();
+    let inputs = weight.len() / outputs;
+    let norm = match norm {
+        Norm::None => None,
+        Norm::Layer => Some(LayerNormParams {
+            weight: get_norm("weight"),
+            bias: get_norm("bias"),
+            eps: NORM_EPS,
+        }),
+        // inference batch norm is an affine transformation, merged into the linear layer
+        Norm::Batch => {
+            let (gamma, beta) = (get_norm("weight"), get_norm("bias"));
+            let (mean, var) = (get_norm("running_mean"), get_norm("running_var"));
+            for o in 0..outputs {
+                let scale = gamma[o] / (var[o] + NORM_EPS).sqrt();
+                weight[o * inputs..(o + 1) * inputs]
+                    .iter_mut()
+                    .for_each(|w| *w *= scale);
+                bias[o] = (bias[o] - mean[o]) * scale + beta[o];
+            }
+            None
+        }
+    };
+    DenseLayer {
+        inputs,
+        outputs,
+        weight,
+        bias,
+        norm,
+        activation,
+    }
+}
+
+fn values(tensor: &Tensor) -> Vec<f32> {
+    Vec::<f32>::try_from(
+        tensor
+            .to_device(Device::Cpu)
+            .to_kind(Kind::Float)
+            .flatten(0, -1),
+    )
+    .unwrap()
+}
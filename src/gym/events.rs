@@ -0,0 +1,97 @@
+use robotics_lib::event::events::Event;
+use robotics_lib::world::environmental_conditions::WeatherType;
+use serde::{Deserialize, Serialize};
+
+/// # Event counters
+///
+/// Events received by the robot since the start of the episode
+///
+/// - `energy_recharged`, `energy_consumed`: energy gained and spent
+/// - `moves`: tiles the robot moved to
+/// - `tiles_updated`: tiles whose content changed
+/// - `added_to_backpack`, `removed_from_backpack`: content put in and taken out of the backpack
+/// - `time_changes`, `day_changes`: ticks of the clock and new days
+/// - `weather_changes`: changes of the weather between two clock ticks
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub struct EventCounters {
+    pub energy_recharged: usize,
+    pub energy_consumed: usize,
+    pub moves: usize,
+    pub tiles_updated: usize,
+    pub added_to_backpack: usize,
+    pub removed_from_backpack: usize,
+    pub time_changes: usize,
+    pub day_changes: usize,
+    pub weather_changes: usize,
+}
+
+impl EventCounters {
+    // count an event, `weather` is the weather of the previous clock tick and gets updated
+    pub fn record(&mut self, event: &Event, weather: &mut Option<WeatherType>) {
+        match event {
+            Event::EnergyRecharged(amount) => self.energy_recharged += amount,
+            Event::EnergyConsumed(amount) => self.energy_consumed += amount,
+            Event::Moved(..) => self.moves += 1,
+            Event::TileContentUpdated(..) => self.tiles_updated += 1,
+            Event::AddedToBackpack(_, amount) => self.added_to_backpack += amount,
+            Event::RemovedFromBackpack(_, amount) => self.removed_from_backpack += amount,
+            Event::TimeChanged(conditions) => {
+                self.time_changes += 1;
+                self.update_weather(conditions.get_weather_condition(), weather);
+            }
+            Event::DayChanged(conditions) => {
+                self.day_changes += 1;
+                self.update_weather(conditions.get_weather_condition(), weather);
+            }
+            _ => {}
+        }
+    }
+
+    fn update_weather(&mut self, current: WeatherType, last: &mut Option<WeatherType>) {
+        if matches!(last, Some(last) if *last != current) {
+            self.weather_changes += 1;
+        }
+        *last = Some(current);
+    }
+
+    // events received after `before` was taken
+    pub fn since(&self, before: &Self) -> Self {
+        Self {
+            energy_recharged: self.energy_recharged - before.energy_recharged,
+            energy_consumed: self.energy_consumed - before.energy_consumed,
+            moves: self.moves - before.moves,
+            tiles_updated: self.tiles_updated - before.tiles_updated,
+            added_to_backpack: self.added_to_backpack - before.added_to_backpack,
+            removed_from_backpack: self.removed_from_backpack - before.removed_from_backpack,
+            time_changes: self.time_changes - before.time_changes,
+            day_changes: self.day_changes - before.day_changes,
+            weather_changes: self.weather_changes - before.weather_changes,
+        }
+    }
+}
+
+/// # Event reward weights
+///
+/// Reward of a tick added for every unit of the matching counter of `EventCounters`
+#[derive(Clone, Copy, Debug)]
+pub struct EventWeights {
+    pub energy_recharged: f64,
+    pub energy_consumed: f64,
+    pub moves: f64,
+    pub tiles_updated: f64,
+    pub added_to_backpack: f64,
+    pub removed_from_backpack: f64,
+    pub weather_changes: f64,
+}
+
+impl EventWeights {
+    pub fn reward(&self, events: &EventCounters) -> f64 {
+        self.energy_recharged * events.energy_recharged as f64
+            + self.energy_consumed * events.energy_consumed as f64
+            + self.moves * events.moves as f64
+            + self.tiles_updated * events.tiles_updated as f64
+            + self.added_to_backpack * events.added_to_backpack as f64
+            + self.removed_from_backpack * events.removed_from_backpack as f64
+            + self.weather_changes * events.weather_changes as f64
+    }
+}
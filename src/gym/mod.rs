@@ -1,15 +1,29 @@
 use std::cell::{Ref, RefCell};
+use std::collections::HashMap;
 use std::rc::Rc;
 
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use robotics_lib::runner::Runner;
+use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
+use robotics_lib::world::tile::{Content, Tile};
+use robotics_lib::world::world_generator::Generator;
 use tch::Tensor;
-use worldgen_unwrap::public::WorldgeneratorUnwrap;
 
-use crate::utils::consts::{N_ACTIONS, N_OBSERVATIONS};
+use crate::utils::consts::N_ACTIONS;
+use crate::utils::functions::observation_size;
+use crate::world::generator::random_conditions;
+use crate::world::map::random_spawn;
+use crate::world::{GeneratedWorld, WorldSource};
+pub mod events;
+pub mod options;
+pub mod recorder;
 pub mod robot;
 pub mod state;
+pub mod task;
 // Implementation following the OpenAI Gym standard
 
+use options::{ResetOptions, StepInfo};
 use robot::GymRobot;
 use state::State;
 
@@ -17,14 +31,19 @@ use state::State;
 ///
 /// - `actions_space`: shape of the output layer
 /// - `observation_space`: shape of the input layer
+/// - `options`: randomization of the start of the episodes, drawn with `rng`
+/// - `info`: start of the current episode
 pub struct GymEnv {
     action_space: i64,
     observation_space: Vec<i64>,
-    generator: WorldgeneratorUnwrap,
+    generator: WorldSource,
     state: Rc<RefCell<State>>,
     runner: Runner,
     coins_destroyed_goal: usize,
     coins_stored_goal: usize,
+    options: ResetOptions,
+    rng: StdRng,
+    info: StepInfo,
 }
 
 pub struct Step {
@@ -32,27 +51,53 @@ pub struct Step {
     pub action: i64,
     pub reward: f64,
     pub done: bool,
+    pub info: StepInfo,
+}
+
+// world generated before the runner, so that the spawn can be moved
+struct Generated(Option<GeneratedWorld>);
+
+impl Generator for Generated {
+    fn gen(
+        &mut self,
+    ) -> (
+        Vec<Vec<Tile>>,
+        (usize, usize),
+        EnvironmentalConditions,
+        f32,
+        Option<HashMap<Content, f32>>,
+    ) {
+        self.0.take().unwrap()
+    }
 }
 
 impl GymEnv {
     pub fn new(
-        mut generator: WorldgeneratorUnwrap,
+        mut generator: WorldSource,
         coins_destroyed_goal: usize,
         coins_stored_goal: usize,
     ) -> Self {
         let state = Rc::new(RefCell::new(State::default()));
-        let mut runner =
-            Runner::new(Box::new(GymRobot::new(state.clone(), coins_destroyed_goal, coins_stored_goal)), &mut generator).unwrap();
-        // let a tick pass to get the near data and init the danger map
-        runner.game_tick().unwrap();
+        let options = ResetOptions::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        let (runner, info) = start(
+            &mut generator,
+            &state,
+            &options,
+            &mut rng,
+            (coins_destroyed_goal, coins_stored_goal),
+        );
         Self {
             action_space: N_ACTIONS,
-            observation_space: vec![N_OBSERVATIONS],
+            observation_space: vec![observation_size()],
             generator,
             runner,
             coins_destroyed_goal,
             state,
             coins_stored_goal,
+            options,
+            rng,
+            info,
         }
     }
     pub fn action_space(&self) -> i64 {
@@ -63,17 +108,13 @@ impl GymEnv {
     }
     pub fn reset(&mut self) -> Tensor {
         *self.state.borrow_mut() = State::default();
-        self.runner = Runner::new(
-            Box::new(GymRobot::new(
-                self.state.clone(),
-                self.coins_destroyed_goal,
-                self.coins_stored_goal,
-            )),
+        (self.runner, self.info) = start(
             &mut self.generator,
-        )
-        .unwrap();
-        // let a tick pass to get the near data and init the danger map
-        self.runner.game_tick().unwrap();
+            &self.state,
+            &self.options,
+            &mut self.rng,
+            (self.coins_destroyed_goal, self.coins_stored_goal),
+        );
         self.state.borrow().build()
     }
     pub fn step(&mut self, action: i64) -> Step {
@@ -85,9 +126,73 @@ impl GymEnv {
             action,
             reward: self.state.borrow().reward,
             done: self.state.borrow().done,
+            info: StepInfo {
+                events: self.state.borrow().events,
+                ..self.info
+            },
         }
     }
+    // randomization of the next resets
+    pub fn set_options(&mut self, options: ResetOptions, seed: u64) {
+        self.options = options;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+    pub fn info(&self) -> StepInfo {
+        self.info
+    }
+    // worlds of the next resets
+    pub fn set_generator(&mut self, generator: WorldSource) {
+        self.generator = generator;
+    }
     pub fn state(&self) -> Ref<State> {
         self.state.borrow()
     }
+    // starting world of the episode
+    pub fn world_snapshot(&mut self) -> Vec<Vec<Tile>> {
+        self.generator.snapshot()
+    }
+}
+
+// runner of a new episode, with the start drawn from the reset options
+fn start(
+    generator: &mut WorldSource,
+    state: &Rc<RefCell<State>>,
+    options: &ResetOptions,
+    rng: &mut StdRng,
+    (coins_destroyed_goal, coins_stored_goal): (usize, usize),
+) -> (Runner, StepInfo) {
+    let (world, mut spawn, mut conditions, score, contents) = generator.gen();
+    if options.random_spawn {
+        spawn = random_spawn(&world, rng).unwrap_or(spawn);
+    }
+    let energy = options.energy.map(|(min, max)| rng.gen_range(min..=max));
+    let coins_destroyed_goal = options
+        .coins_destroyed_goal
+        .map_or(coins_destroyed_goal, |(min, max)| rng.gen_range(min..=max));
+    let coins_stored_goal = options
+        .coins_stored_goal
+        .map_or(coins_stored_goal, |(min, max)| rng.gen_range(min..=max));
+    if options.random_weather {
+        conditions = random_conditions(rng);
+    }
+    let mut runner = Runner::new(
+        Box::new(GymRobot::new(
+            state.clone(),
+            coins_destroyed_goal,
+            coins_stored_goal,
+            energy,
+        )),
+        &mut Generated(Some((world, spawn, conditions, score, contents))),
+    )
+    .unwrap();
+    // let a tick pass to get the near data and init the danger map
+    runner.game_tick().unwrap();
+    let info = StepInfo {
+        spawn,
+        energy: state.borrow().energy,
+        coins_destroyed_goal,
+        coins_stored_goal,
+        events: state.borrow().events,
+    };
+    (runner, info)
 }
@@ -0,0 +1,38 @@
+use crate::gym::events::EventCounters;
+use serde::{Deserialize, Serialize};
+
+/// # Reset options
+///
+/// Randomization of the start of every episode, the ranges are inclusive
+///
+/// - `random_spawn`: spawn on a random free tile from which a coin and a bank can be reached,
+///   otherwise on the spawn of the world
+/// - `energy`: initial energy, full if not set
+/// - `coins_destroyed_goal`, `coins_stored_goal`: goals, the ones of the environment if not set
+/// - `random_weather`: random forecast and starting hour, otherwise the conditions of the world
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub struct ResetOptions {
+    pub random_spawn: bool,
+    pub energy: Option<(usize, usize)>,
+    pub coins_destroyed_goal: Option<(usize, usize)>,
+    pub coins_stored_goal: Option<(usize, usize)>,
+    pub random_weather: bool,
+}
+
+/// # Step info
+///
+/// Start of the current episode as drawn from the reset options
+/// and the events received since then
+///
+/// - `spawn`: starting position of the robot
+/// - `energy`: initial energy
+/// - `coins_destroyed_goal`, `coins_stored_goal`: goals of the episode
+/// - `events`: counters of the events of the episode
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub struct StepInfo {
+    pub spawn: (usize, usize),
+    pub energy: usize,
+    pub coins_destroyed_goal: usize,
+    pub coins_stored_goal: usize,
+    pub events: EventCounters,
+}
@@ -0,0 +1,97 @@
+use crate::gym::state::{weather_name, State};
+use robotics_lib::world::tile::{Content, Tile};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+/// # Episode recording
+///
+/// - `map`: name of the map the episode was played on
+/// - `worker`: worker that played the episode
+/// - `world`: world as it was at the start of the episode
+/// - `frames`: one frame for the reset and one for every step
+#[derive(Serialize, Deserialize)]
+pub struct Recording {
+    pub map: String,
+    pub worker: usize,
+    pub world: Vec<Vec<Tile>>,
+    pub frames: Vec<Frame>,
+}
+
+/// # Recorded frame
+///
+/// Snapshot of the environment right after `action` has been executed,
+/// the first frame of a recording holds the state after the reset (`action` = -1)
+#[derive(Serialize, Deserialize)]
+pub struct Frame {
+    pub iteration: usize,
+    pub action: i64,
+    pub reward: f64,
+    pub acc_reward: f64,
+    pub done: bool,
+    pub position: (usize, usize),
+    pub energy: usize,
+    #[serde(default)]
+    pub weather: String,
+    #[serde(default)]
+    pub time: String,
+    pub backpack: Vec<(Content, usize)>,
+    pub view: Vec<Vec<Option<Tile>>>,
+    pub danger: [f64; 4],
+    pub coin_dir: [f64; 4],
+    pub bank_dir: [f64; 4],
+    pub coin_adj: [f64; 4],
+    pub bank_adj: [f64; 4],
+}
+
+impl Frame {
+    pub fn capture(iteration: usize, acc_reward: f64, state: &State) -> Self {
+        Self {
+            iteration,
+            action: state.action,
+            reward: state.reward,
+            acc_reward,
+            done: state.done,
+            position: state.position,
+            energy: state.energy,
+            weather: weather_name(state),
+            time: state.time.clone(),
+            backpack: state
+                .backpack
+                .iter()
+                .map(|(content, amount)| (content.clone(), *amount))
+                .collect(),
+            view: state.view.clone(),
+            danger: state.danger,
+            coin_dir: state.coin_dir,
+            bank_dir: state.bank_dir,
+            coin_adj: state.coin_adj,
+            bank_adj: state.bank_adj,
+        }
+    }
+}
+
+impl Recording {
+    pub fn new(map: &str, worker: usize, world: Vec<Vec<Tile>>) -> Self {
+        Self {
+            map: map.to_string(),
+            worker,
+            world,
+            frames: vec![],
+        }
+    }
+
+    pub fn push(&mut self, frame: Frame) {
+        self.frames.push(frame);
+    }
+
+    pub fn save(&self, path: &str) {
+        let file = File::create(path).unwrap();
+        serde_json::to_writer(BufWriter::new(file), self).unwrap();
+    }
+
+    pub fn load(path: &str) -> Self {
+        let file = File::open(path).unwrap();
+        serde_json::from_reader(BufReader::new(file)).unwrap()
+    }
+}
@@ -1,18 +1,28 @@
+use crate::gym::events::EventCounters;
 use crate::gym::state::State;
 use crate::utils::consts::{
-    BASE_GO_REWARD, COEFFICIENT_X_COINS, LIM_F_COINS, LOG_BASE_COINS,
-    PERCENTAGE_ENERGY_RESERVED_FOR_SCANNING, REWARD_FOR_ILLEGAL_ACTION,
+    BASE_GO_REWARD, COEFFICIENT_X_COINS, EVENT_REWARDS, FULL_DEPOSIT_REWARD, GRID_OBSERVATION,
+    LIM_F_COINS, LOG_BASE_COINS, NEAREST_TARGETS, PERCENTAGE_ENERGY_RESERVED_FOR_SCANNING,
+    REWARD_FOR_ILLEGAL_ACTION, STARVED_PATIENCE, STARVED_REWARD, TASK, WAIT, WAIT_ACTION,
+    WAIT_REWARD, WEATHER_OBSERVATION,
+};
+use crate::utils::functions::{
+    capacity_left, reward_fn, scan_reward, sky_features, update_closest, update_danger,
+    update_grid, update_nearest,
 };
-use crate::utils::functions::{reward_fn, scan_reward, update_closest, update_danger};
 use robotics_lib::energy::Energy;
 use robotics_lib::event::events::Event;
-use robotics_lib::interface::{destroy, go, one_direction_view, put, robot_view, Direction};
+use robotics_lib::interface::{
+    destroy, go, look_at_sky, one_direction_view, put, robot_view, Direction,
+};
 use robotics_lib::runner::backpack::BackPack;
 use robotics_lib::runner::{Robot, Runnable};
 use robotics_lib::world::coordinates::Coordinate;
+use robotics_lib::world::environmental_conditions::WeatherType;
 use robotics_lib::world::tile::Content;
 use robotics_lib::world::World;
 use std::cell::RefCell;
+use std::collections::HashSet;
 use std::rc::Rc;
 
 pub struct GymRobot {
@@ -24,6 +34,10 @@ pub struct GymRobot {
     pub coins_stored: usize,
     pub coins_destroyed_goal: usize,
     pub coins_stored_goal: usize,
+    pub visited: HashSet<(usize, usize)>,
+    starved_ticks: usize,
+    last_weather: Option<WeatherType>,
+    rewarded_events: EventCounters,
     setup: bool,
 }
 
@@ -32,39 +46,113 @@ impl GymRobot {
         state: Rc<RefCell<State>>,
         coins_destroyed_goal: usize,
         coins_stored_goal: usize,
+        energy: Option<usize>,
     ) -> Self {
+        let mut robot = Robot::new();
+        if let Some(energy) = energy {
+            robot.energy = Energy::new(energy);
+        }
         Self {
-            robot: Robot::new(),
+            robot,
             closest_coin: None,
             closest_bank: None,
             coins_destroyed: 0,
             coins_stored: 0,
             coins_destroyed_goal,
             coins_stored_goal,
+            visited: HashSet::new(),
+            starved_ticks: 0,
+            last_weather: None,
+            rewarded_events: EventCounters::default(),
             setup: true,
             state,
         }
     }
 
     fn can_destroy_or_put(&self) -> bool {
-        // can destroy since it has a coin adj
+        // can destroy since it has a content to collect adj
         self.state.borrow().coin_adj.contains(&1.)
-            // can put since it has a bank adj and at least a coin in the backpack
-            || self.state.borrow().bank_adj.contains(&1.)
-                && *self
-                    .get_backpack()
-                    .get_contents()
-                    .get(&Content::Coin(0))
-                    .unwrap()
-                    != 0
+            // can put since it has a deposit adj and at least a collected content in the backpack
+            || self.state.borrow().bank_adj.contains(&1.) && self.collected() != 0
+    }
+
+    // collected content in the backpack
+    fn collected(&self) -> usize {
+        self.get_backpack()
+            .get_contents()
+            .get(&TASK.collect.to_default())
+            .copied()
+            .unwrap_or(0)
+    }
+
+    // content of the tile next to the robot in the given direction
+    fn adjacent_content(&self, world: &mut World, dir: &Direction) -> Option<Content> {
+        let view = robot_view(self, world);
+        let (i, j) = match dir {
+            Direction::Up => (0, 1),
+            Direction::Right => (1, 2),
+            Direction::Down => (2, 1),
+            Direction::Left => (1, 0),
+        };
+        view[i][j].as_ref().map(|tile| tile.content.clone())
+    }
+
+    fn scan_distance(&self) -> usize {
+        (self.get_energy().get_energy_level() as f64 / 3. * PERCENTAGE_ENERGY_RESERVED_FOR_SCANNING)
+            .floor() as usize
+    }
+
+    // no direction to move to, nothing to destroy or put and not enough energy to scan
+    fn starved(&self) -> bool {
+        self.state
+            .borrow()
+            .danger
+            .iter()
+            .all(|&danger| danger == 1.)
+            && !self.can_destroy_or_put()
+            && self.scan_distance() < 2
     }
 
     fn wrong_dir(&self, i: usize) -> bool {
         self.state.borrow().coin_dir[i] != 1. && self.state.borrow().bank_dir[i] != 1.
     }
 
+    // snapshot the robot data that isn't part of the observation
+    fn sync_state(&mut self, world: &mut World) {
+        let position = (
+            self.get_coordinate().get_row(),
+            self.get_coordinate().get_col(),
+        );
+        self.visited.insert(position);
+        if let Some(size) = GRID_OBSERVATION {
+            update_grid(self, world, size);
+        }
+        if let Some(k) = NEAREST_TARGETS {
+            update_nearest(self, world, k);
+        }
+        let view = robot_view(self, world);
+        let conditions = look_at_sky(world);
+        let mut state = self.state.borrow_mut();
+        state.weather = Some(conditions.get_weather_condition());
+        state.time = conditions.get_time_of_day_string();
+        if WEATHER_OBSERVATION {
+            state.sky = sky_features(&conditions);
+        }
+        state.position = position;
+        state.energy = self.get_energy().get_energy_level();
+        state.backpack = self.get_backpack().get_contents().clone();
+        state.coins_destroyed = self.coins_destroyed;
+        state.coins_stored = self.coins_stored;
+        state.view = view;
+    }
+
     pub fn step(&mut self, world: &mut World) -> f64 {
         let action = self.state.borrow().action;
+        if WAIT_ACTION && action == WAIT {
+            // wait, the energy regenerates during the tick
+            update_danger(self, world);
+            return WAIT_REWARD;
+        }
         let dir = match action % 4 {
             0 => Direction::Up,
             1 => Direction::Right,
@@ -92,7 +180,13 @@ impl GymRobot {
                 }
             }
             1 => {
-                // destroy
+                // destroy, only the content of the task
+                if !self
+                    .adjacent_content(world, &dir)
+                    .map_or(false, |content| TASK.is_collect(&content))
+                {
+                    return REWARD_FOR_ILLEGAL_ACTION;
+                }
                 if let Ok(amt_dst) = destroy(self, world, dir) {
                     update_closest(self, world);
                     self.coins_destroyed += amt_dst;
@@ -115,48 +209,21 @@ impl GymRobot {
             }
             2 => {
                 // put
-                // check that the put target is valid (Bank)
-                let tmp_adj = robot_view(self, world);
-                match &dir {
-                    Direction::Up => match &tmp_adj[0][1] {
-                        None => return REWARD_FOR_ILLEGAL_ACTION,
-                        Some(tile) => {
-                            if tile.content.to_default() != Content::Bank(0..0) {
-                                return REWARD_FOR_ILLEGAL_ACTION;
-                            }
-                        }
-                    },
-                    Direction::Right => match &tmp_adj[1][2] {
-                        None => return REWARD_FOR_ILLEGAL_ACTION,
-                        Some(tile) => {
-                            if tile.content.to_default() != Content::Bank(0..0) {
-                                return REWARD_FOR_ILLEGAL_ACTION;
-                            }
-                        }
-                    },
-                    Direction::Down => match &tmp_adj[2][1] {
-                        None => return REWARD_FOR_ILLEGAL_ACTION,
-                        Some(tile) => {
-                            if tile.content.to_default() != Content::Bank(0..0) {
-                                return REWARD_FOR_ILLEGAL_ACTION;
-                            }
-                        }
-                    },
-                    Direction::Left => match &tmp_adj[1][0] {
-                        None => return REWARD_FOR_ILLEGAL_ACTION,
-                        Some(tile) => {
-                            if tile.content.to_default() != Content::Bank(0..0) {
-                                return REWARD_FOR_ILLEGAL_ACTION;
-                            }
-                        }
-                    },
+                // check that the put target is valid (the deposit of the task)
+                let deposit = match self.adjacent_content(world, &dir) {
+                    Some(content) if TASK.is_deposit(&content) => content,
+                    _ => return REWARD_FOR_ILLEGAL_ACTION,
+                };
+                // only what the deposit can still hold, the rest stays in the backpack
+                let mut amount = self.collected();
+                if let Some((left, _)) = capacity_left(&deposit) {
+                    if left == 0 {
+                        self.state.borrow_mut().full_deposit = true;
+                        return FULL_DEPOSIT_REWARD;
+                    }
+                    amount = amount.min(left);
                 }
-                let amount = *self
-                    .get_backpack()
-                    .get_contents()
-                    .get(&Content::Coin(0))
-                    .unwrap();
-                if let Ok(amt_put) = put(self, world, Content::Coin(0), amount, dir) {
+                if let Ok(amt_put) = put(self, world, TASK.collect.to_default(), amount, dir) {
                     update_closest(self, world);
                     self.coins_stored += amt_put;
                     if self.coins_stored >= self.coins_stored_goal {
@@ -181,9 +248,7 @@ impl GymRobot {
                 if self.can_destroy_or_put() {
                     return REWARD_FOR_ILLEGAL_ACTION;
                 }
-                let distance = (self.get_energy().get_energy_level() as f64 / 3.
-                    * PERCENTAGE_ENERGY_RESERVED_FOR_SCANNING)
-                    .floor() as usize;
+                let distance = self.scan_distance();
                 if distance < 2 {
                     REWARD_FOR_ILLEGAL_ACTION
                 } else {
@@ -202,15 +267,41 @@ impl Runnable for GymRobot {
         if self.setup {
             update_danger(self, world);
             update_closest(self, world);
+            self.sync_state(world);
+            self.rewarded_events = self.state.borrow().events;
             self.setup = false;
             return;
         }
-        let reward = self.step(world);
+        self.state.borrow_mut().full_deposit = false;
+        // energy regenerated at the end of the last tick, waiting isn't starving while it rises
+        let recharged = self.get_energy().get_energy_level() > self.state.borrow().energy;
+        let mut reward = self.step(world);
+        self.state.borrow_mut().illegal = reward == REWARD_FOR_ILLEGAL_ACTION;
+        // reward terms from the events since the last action, the ones sent by the runner
+        // at the end of a tick (recharge, time) are rewarded with the next action
+        let events = self.state.borrow().events;
+        reward += EVENT_REWARDS.reward(&events.since(&self.rewarded_events));
+        self.rewarded_events = events;
+        if self.starved() && !(WAIT_ACTION && recharged) {
+            self.starved_ticks += 1;
+        } else {
+            self.starved_ticks = 0;
+        }
+        if self.starved_ticks > STARVED_PATIENCE && !self.state.borrow().done {
+            let mut state = self.state.borrow_mut();
+            state.done = true;
+            state.starved = true;
+            reward = STARVED_REWARD;
+        }
         self.state.borrow_mut().reward = reward;
+        self.sync_state(world);
     }
 
-    fn handle_event(&mut self, _event: Event) {
-        // TODO
+    fn handle_event(&mut self, event: Event) {
+        self.state
+            .borrow_mut()
+            .events
+            .record(&event, &mut self.last_weather);
     }
 
     fn get_energy(&self) -> &Energy {
@@ -1,15 +1,44 @@
-use tch::{Device, Kind, Tensor};
+use crate::gym::events::EventCounters;
+use robotics_lib::world::environmental_conditions::WeatherType;
+use robotics_lib::world::tile::{Content, Tile};
+use std::collections::HashMap;
+use tch::{Device, Tensor};
 
+/// # Gym state
+///
+/// Besides the observation vectors it keeps a snapshot of the robot
+/// (position, energy, backpack and 3x3 view) and the task progress
+/// taken at the end of every tick, `grid` is only filled when `GRID_OBSERVATION` is set
+/// and `sky` when `WEATHER_OBSERVATION` is set, `starved` tells apart the episodes
+/// that ended because the robot ran out of energy from the completed ones,
+/// `events` counts the events received by the robot during the episode,
+/// `nearest` is only filled when `NEAREST_TARGETS` is set and `full_deposit` flags a put
+/// into a deposit without capacity left
 #[derive(Debug)]
 pub struct State {
     pub action: i64,
     pub reward: f64,
     pub done: bool,
+    pub illegal: bool,
+    pub starved: bool,
+    pub coins_destroyed: usize,
+    pub coins_stored: usize,
     pub danger: [f64; 4],
     pub coin_dir: [f64; 4],
     pub bank_dir: [f64; 4],
     pub coin_adj: [f64; 4],
     pub bank_adj: [f64; 4],
+    pub position: (usize, usize),
+    pub energy: usize,
+    pub backpack: HashMap<Content, usize>,
+    pub view: Vec<Vec<Option<Tile>>>,
+    pub grid: Vec<f64>,
+    pub weather: Option<WeatherType>,
+    pub time: String,
+    pub sky: Vec<f64>,
+    pub nearest: Vec<f64>,
+    pub full_deposit: bool,
+    pub events: EventCounters,
 }
 
 impl Default for State {
@@ -18,29 +47,55 @@ impl Default for State {
             action: -1,
             reward: 0.0,
             done: false,
+            illegal: false,
+            starved: false,
+            coins_destroyed: 0,
+            coins_stored: 0,
             danger: [0.0; 4],
             coin_dir: [0.0; 4],
             bank_dir: [0.0; 4],
             coin_adj: [0.0; 4],
             bank_adj: [0.0; 4],
+            position: (0, 0),
+            energy: 0,
+            backpack: HashMap::new(),
+            view: vec![],
+            grid: vec![],
+            weather: None,
+            time: String::new(),
+            sky: vec![],
+            nearest: vec![],
+            full_deposit: false,
+            events: EventCounters::default(),
         }
     }
 }
 
 impl State {
     pub fn build(&self) -> Tensor {
-        Tensor::from_slice(
-            &self
-                .danger
-                .iter()
-                .chain(&self.coin_dir)
-                .chain(&self.bank_dir)
-                .chain(&self.coin_adj)
-                .chain(&self.bank_adj)
-                .copied()
-                .collect::<Vec<f64>>(),
-        )
-        .to_kind(Kind::Float)
-        .to(Device::cuda_if_available())
+        Tensor::from_slice(&self.features()).to(Device::cuda_if_available())
     }
+
+    // observation as plain floats, for the policies that don't use tch
+    pub fn features(&self) -> Vec<f32> {
+        self.danger
+            .iter()
+            .chain(&self.coin_dir)
+            .chain(&self.bank_dir)
+            .chain(&self.coin_adj)
+            .chain(&self.bank_adj)
+            .chain(&self.sky)
+            .chain(&self.nearest)
+            .chain(&self.grid)
+            .map(|&x| x as f32)
+            .collect()
+    }
+}
+
+// weather of the last tick, empty before the first one
+pub fn weather_name(state: &State) -> String {
+    state
+        .weather
+        .as_ref()
+        .map_or(String::new(), |weather| format!("{:?}", weather))
 }
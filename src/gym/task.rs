@@ -0,0 +1,85 @@
+use robotics_lib::world::tile::Content;
+
+/// # Task
+///
+/// Content the robot collects and where it deposits it, the coin and bank observations,
+/// counters and goals refer to these two contents
+///
+/// - `collect`: content destroyed and carried in the backpack
+/// - `deposit`: content the collected one is put into
+/// - `collect_goal`: collected amount that completes the task
+/// - `deposit_goal`: deposited amount that completes the task
+#[derive(Clone, Debug)]
+pub struct Task {
+    pub collect: Content,
+    pub deposit: Content,
+    pub collect_goal: usize,
+    pub deposit_goal: usize,
+}
+
+impl Task {
+    // contents tracked by the journal
+    pub fn targets(&self) -> [Content; 2] {
+        [self.collect.to_default(), self.deposit.to_default()]
+    }
+
+    pub fn is_collect(&self, content: &Content) -> bool {
+        content.to_default() == self.collect.to_default()
+    }
+
+    pub fn is_deposit(&self, content: &Content) -> bool {
+        content.to_default() == self.deposit.to_default()
+    }
+
+    // names used in the observation layout
+    pub fn names(&self) -> (String, String) {
+        (content_name(&self.collect), content_name(&self.deposit))
+    }
+
+    // content to collect holding `amount` units, placed by the procedural generator
+    pub fn collect_content(&self, amount: usize) -> Content {
+        match self.collect.to_default() {
+            Content::Rock(_) => Content::Rock(amount),
+            Content::Tree(_) => Content::Tree(amount),
+            Content::Garbage(_) => Content::Garbage(amount),
+            Content::Coin(_) => Content::Coin(amount),
+            Content::Water(_) => Content::Water(amount),
+            Content::Fish(_) => Content::Fish(amount),
+            Content::Bush(_) => Content::Bush(amount),
+            content => content,
+        }
+    }
+
+    // deposit holding up to `capacity` units, placed by the procedural generator
+    pub fn deposit_content(&self, capacity: usize) -> Content {
+        match self.deposit.to_default() {
+            Content::Bank(_) => Content::Bank(0..capacity),
+            Content::Crate(_) => Content::Crate(0..capacity),
+            Content::Bin(_) => Content::Bin(0..capacity),
+            content => content,
+        }
+    }
+}
+
+// units held by a content, 1 for the contents without an amount
+pub fn content_amount(content: &Content) -> usize {
+    match content {
+        Content::Rock(amount)
+        | Content::Tree(amount)
+        | Content::Garbage(amount)
+        | Content::Coin(amount)
+        | Content::Water(amount)
+        | Content::Fish(amount)
+        | Content::Bush(amount) => *amount,
+        _ => 1,
+    }
+}
+
+// lowercase variant name of a content
+fn content_name(content: &Content) -> String {
+    format!("{:?}", content.to_default())
+        .split('(')
+        .next()
+        .unwrap()
+        .to_lowercase()
+}
@@ -0,0 +1,13 @@
+pub mod export;
+#[cfg(feature = "torch")]
+pub mod gym;
+#[cfg(feature = "torch")]
+pub mod model;
+#[cfg(feature = "torch")]
+pub mod policy;
+#[cfg(feature = "torch")]
+pub mod run;
+#[cfg(feature = "torch")]
+pub mod utils;
+#[cfg(feature = "torch")]
+pub mod world;
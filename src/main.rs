@@ -1,20 +1,157 @@
-use crate::run::{eval, init, load, train};
-use std::env;
+use clap::{Parser, Subcommand};
+use ddpg_robot::run::{
+    bench, compare_runs, eval, export, generate_map, init, list_maps, list_runs, load, matrix,
+    replay, show_run, train, validate_map, verify,
+};
+use ddpg_robot::utils::consts::{BENCH_RESETS, EVAL_EPISODES, MAPS};
+use ddpg_robot::world::generator::MapParams;
+use std::path::PathBuf;
 
-mod gym;
-mod model;
-mod run;
-mod utils;
+#[derive(Parser)]
+struct Cli {
+    #[command(subcommand)]
+    mode: Mode,
+}
+
+#[derive(Subcommand)]
+enum Mode {
+    /// Create and inspect the maps
+    Init {
+        #[command(subcommand)]
+        command: InitCommand,
+    },
+    Train,
+    Load,
+    /// Evaluate the saved models greedily, summarizing the results over many episodes
+    Eval {
+        /// Episodes per map and seed
+        #[arg(long, default_value_t = EVAL_EPISODES)]
+        episodes: usize,
+        /// Comma separated seeds, every seed runs its own set of episodes
+        #[arg(long, value_delimiter = ',', default_value = "0")]
+        seeds: Vec<u64>,
+        /// Draw the weather of every episode from its seed and break the results down by weather
+        #[arg(long)]
+        random_weather: bool,
+        /// Run to evaluate, the latest one by default
+        #[arg(long)]
+        run: Option<String>,
+    },
+    /// Evaluate every saved model on every map to check how the policies generalize
+    Matrix {
+        /// Episodes per model and map
+        #[arg(long, default_value_t = EVAL_EPISODES)]
+        episodes: usize,
+        /// Seed of the spawns of the episodes
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// Map files to evaluate on besides the ones in the maps directory
+        maps: Vec<PathBuf>,
+        /// Run whose models are evaluated, the latest one by default
+        #[arg(long)]
+        run: Option<String>,
+    },
+    /// Step through the episode recorded by the last evaluation of a worker
+    Replay {
+        worker: usize,
+        /// Run of the evaluation, the latest one by default
+        #[arg(long)]
+        run: Option<String>,
+    },
+    /// Export the saved actors as ONNX and portable JSON weights
+    Export {
+        /// Comma separated workers to export, all of them by default
+        #[arg(long, value_delimiter = ',')]
+        workers: Vec<usize>,
+        /// Run to export, the latest one by default
+        #[arg(long)]
+        run: Option<String>,
+    },
+    /// Check that the exported policies pick the same actions as the TorchScript actors
+    Verify {
+        /// Greedy episodes per map whose observations are compared
+        #[arg(long, default_value_t = EVAL_EPISODES)]
+        episodes: usize,
+        /// Run to verify, the latest one by default
+        #[arg(long)]
+        run: Option<String>,
+    },
+    /// Measure the resets per second of a map with and without the world cache
+    Bench {
+        /// Map file, or name of a map in the maps directory
+        #[arg(default_value = MAPS[0])]
+        map: PathBuf,
+        /// Resets timed for each variant
+        #[arg(long, default_value_t = BENCH_RESETS)]
+        resets: usize,
+    },
+    /// Inspect the training runs
+    Runs {
+        #[command(subcommand)]
+        command: RunsCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum InitCommand {
+    /// Open the interactive world generator
+    Edit,
+    /// Generate a map and save it in the maps directory
+    Generate {
+        /// Name of the map file, without extension
+        name: String,
+        #[command(flatten)]
+        params: MapParams,
+    },
+    /// List the maps with their statistics
+    List,
+    /// Check that a map has a coin and a bank the robot can reach
+    Validate {
+        /// Map file, or name of a map in the maps directory
+        map: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum RunsCommand {
+    /// List the runs with their best evaluation scores
+    List,
+    /// Show the configuration, models and last evaluation of a run
+    Show { id: String },
+    /// Compare the configurations and evaluation results of some runs
+    Compare { ids: Vec<String> },
+}
 
 fn main() {
-    match env::args().nth(1) {
-        None => println!("Missing mode"),
-        Some(mode) => match mode.as_str() {
-            "init" => init(),
-            "train" => train(),
-            "load" => load(),
-            "eval" => eval(),
-            _ => println!("Wrong mode provided: {}", mode),
+    match Cli::parse().mode {
+        Mode::Init { command } => match command {
+            InitCommand::Edit => init(),
+            InitCommand::Generate { name, params } => generate_map(name, params),
+            InitCommand::List => list_maps(),
+            InitCommand::Validate { map } => validate_map(map),
+        },
+        Mode::Train => train(),
+        Mode::Load => load(),
+        Mode::Eval {
+            episodes,
+            seeds,
+            random_weather,
+            run,
+        } => eval(episodes, seeds, random_weather, run),
+        Mode::Matrix {
+            episodes,
+            seed,
+            maps,
+            run,
+        } => matrix(episodes, seed, maps, run),
+        Mode::Replay { worker, run } => replay(worker, run),
+        Mode::Export { workers, run } => export(workers, run),
+        Mode::Verify { episodes, run } => verify(episodes, run),
+        Mode::Bench { map, resets } => bench(map, resets),
+        Mode::Runs { command } => match command {
+            RunsCommand::List => list_runs(),
+            RunsCommand::Show { id } => show_run(id),
+            RunsCommand::Compare { ids } => compare_runs(ids),
         },
     }
 }
@@ -1,12 +1,15 @@
-use crate::utils::functions::create_network;
+use crate::model::encoder::ConvEncoder;
+use crate::model::spec::NetworkSpec;
+use crate::utils::functions::{create_network, weights_path};
 use tch::kind::{FLOAT_CPU, FLOAT_CUDA};
-use tch::nn::{Adam, Optimizer, OptimizerConfig, Sequential, VarStore};
+use tch::nn::{Adam, ModuleT, Optimizer, OptimizerConfig, SequentialT, VarStore};
 use tch::{CModule, Cuda, Device, Tensor};
 
 pub struct Actor {
     save_path: String,
     vs: VarStore,
-    network: Sequential,
+    encoder: Option<ConvEncoder>,
+    network: SequentialT,
     device: Device,
     observation_space: usize,
     action_space: usize,
@@ -18,22 +21,23 @@ impl Actor {
         observation_space: usize,
         action_space: usize,
         lr: f64,
-        hidden_layers: &[i64],
+        spec: &NetworkSpec,
         save_path: String,
     ) -> Self {
         let device = Device::cuda_if_available();
         let vs = VarStore::new(device);
         let optimizer = Adam::default().build(&vs, lr).unwrap();
         let p = &vs.root();
-        let network = create_network(
-            p,
-            observation_space as i64,
-            action_space as i64,
-            hidden_layers,
-            false,
-        );
+        let encoder = spec
+            .encoder
+            .map(|encoder| ConvEncoder::new(&(p / "encoder"), observation_space as i64, &encoder));
+        let input = encoder
+            .as_ref()
+            .map_or(observation_space as i64, |encoder| encoder.output_dim());
+        let network = create_network(p, input, action_space as i64, spec);
         Self {
             save_path,
+            encoder,
             device: p.device(),
             network,
             observation_space,
@@ -44,10 +48,33 @@ impl Actor {
     }
 
     pub fn forward(&self, obs: &Tensor) -> Tensor {
-        obs.to_device(self.device).apply(&self.network)
+        self.forward_t(obs, false)
+    }
+
+    // single observations are run as a batch of one so that batch norm and the encoder can be used
+    pub fn forward_t(&self, obs: &Tensor, train: bool) -> Tensor {
+        let single = obs.dim() == 1;
+        let mut xs = obs.to_device(self.device);
+        if single {
+            xs = xs.unsqueeze(0);
+        }
+        if let Some(encoder) = &self.encoder {
+            xs = encoder.forward_t(&xs, train);
+        }
+        let actions = self.network.forward_t(&xs, train);
+        if single {
+            actions.squeeze_dim(0)
+        } else {
+            actions
+        }
     }
 
     pub fn save(&mut self) {
+        let path = self.save_path.clone();
+        self.save_to(&path);
+    }
+
+    pub fn save_to(&mut self, path: &str) {
         // save via tracing
 
         // disable gradient tracking
@@ -66,8 +93,9 @@ impl Actor {
             &mut forward_fn,
         )
         .unwrap();
-        // save the module
-        cmodule.save(&self.save_path).unwrap();
+        // save the module and the weights used by the export
+        cmodule.save(path).unwrap();
+        self.vs.save(weights_path(path)).unwrap();
         self.vs.unfreeze();
     }
 
@@ -2,10 +2,14 @@ use tch::Kind::Float;
 use tch::{no_grad, Tensor};
 
 use crate::model::actor::Actor;
-use crate::model::critic::Critic;
+use crate::model::critic::{Critic, CriticHead};
+use crate::model::episodic::Episodic;
+use crate::model::learner::Learner;
+use crate::model::loss::{categorical_projection, CriticLoss};
 use crate::model::memory::ReplayMemory;
+use crate::model::metrics::TrainMetrics;
 use crate::model::noise::Noise;
-use crate::utils::functions::update_vs;
+use crate::utils::functions::{grad_norm, update_vs};
 
 pub struct Agent {
     actor: Actor,
@@ -17,6 +21,9 @@ pub struct Agent {
     train: bool,
     gamma: f64,
     tau: f64,
+    critic_loss: CriticLoss,
+    actor_grad_clip: Option<f64>,
+    critic_grad_clip: Option<f64>,
 }
 
 impl Agent {
@@ -30,6 +37,9 @@ impl Agent {
         train: bool,
         gamma: f64,
         tau: f64,
+        critic_loss: CriticLoss,
+        actor_grad_clip: Option<f64>,
+        critic_grad_clip: Option<f64>,
     ) -> Self {
         let replay_memory = ReplayMemory::new(
             mem_dim as i64,
@@ -46,10 +56,19 @@ impl Agent {
             train,
             gamma,
             tau,
+            critic_loss,
+            actor_grad_clip,
+            critic_grad_clip,
         }
     }
+}
+
+impl Episodic for Agent {
+    fn reset(&mut self) {}
+}
 
-    pub fn actions(&mut self, obs: &Tensor) -> Tensor {
+impl Learner for Agent {
+    fn actions(&mut self, obs: &Tensor) -> Tensor {
         //let obs = obs + self.noise.sample();
         let mut actions = no_grad(|| self.actor.forward(&obs));
         if self.train {
@@ -58,38 +77,79 @@ impl Agent {
         actions
     }
 
-    pub fn remember(&mut self, obs: &Tensor, actions: &Tensor, reward: &Tensor, next_obs: &Tensor) {
+    // actions without exploration noise
+    fn greedy_actions(&mut self, obs: &Tensor) -> Tensor {
+        no_grad(|| self.actor.forward(obs))
+    }
+
+    fn remember(&mut self, obs: &Tensor, actions: &Tensor, reward: &Tensor, next_obs: &Tensor) {
         self.replay_memory.push(obs, actions, reward, next_obs);
     }
 
-    pub fn train(&mut self, batch_size: usize) {
+    fn train(&mut self, batch_size: usize) -> Option<TrainMetrics> {
         let (states, actions, rewards, next_states) =
             match self.replay_memory.random_batch(batch_size) {
                 Some(v) => v,
-                _ => return, // Not enough samples for training yet.
+                _ => return None, // Not enough samples for training yet.
             };
 
         let mut q_target = self
             .critic_target
             .forward(&next_states, &self.actor_target.forward(&next_states));
-        q_target = rewards + (self.gamma * q_target).detach();
+        q_target = &rewards + (self.gamma * q_target).detach();
 
-        let q = self.critic.forward(&states, &actions);
+        let q = self.critic.forward_t(&states, &actions, true);
 
-        let diff = q_target - q;
-        let critic_loss = (&diff * &diff).mean(Float);
+        let td_error = (&q_target - &q).detach().abs();
+        let critic_loss = match self.critic.head() {
+            // cross entropy with the projected target distribution
+            CriticHead::Categorical { v_min, v_max, .. } => {
+                let target = no_grad(|| {
+                    let next_probs = self
+                        .critic_target
+                        .log_probs_t(
+                            &next_states,
+                            &self.actor_target.forward(&next_states),
+                            false,
+                        )
+                        .exp();
+                    categorical_projection(
+                        &next_probs,
+                        &rewards,
+                        self.gamma,
+                        self.critic.support().unwrap(),
+                        v_min,
+                        v_max,
+                    )
+                });
+                let log_probs = self.critic.log_probs_t(&states, &actions, true);
+                -(target * log_probs)
+                    .sum_dim_intlist([1].as_slice(), false, Float)
+                    .mean(Float)
+            }
+            _ => self.critic_loss.compute(&q, &q_target),
+        };
 
         self.critic.optimizer_mut().zero_grad();
         critic_loss.backward();
+        let critic_grad_norm = grad_norm(self.critic.var_store());
+        if let Some(max) = self.critic_grad_clip {
+            self.critic.optimizer_mut().clip_grad_norm(max);
+        }
         self.critic.optimizer_mut().step();
 
+        // the critic is only evaluated, its running statistics come from the replayed actions
         let actor_loss = -self
             .critic
-            .forward(&states, &self.actor.forward(&states))
+            .forward_t(&states, &self.actor.forward_t(&states, true), false)
             .mean(Float);
 
         self.actor.optimizer_mut().zero_grad();
         actor_loss.backward();
+        let actor_grad_norm = grad_norm(self.actor.var_store());
+        if let Some(max) = self.actor_grad_clip {
+            self.actor.optimizer_mut().clip_grad_norm(max);
+        }
         self.actor.optimizer_mut().step();
 
         update_vs(
@@ -102,9 +162,30 @@ impl Agent {
             self.actor.var_store(),
             self.tau,
         );
+
+        Some(TrainMetrics {
+            critic_loss: critic_loss.double_value(&[]),
+            actor_loss: actor_loss.double_value(&[]),
+            critic_grad_norm,
+            actor_grad_norm,
+            mean_q: q.mean(Float).double_value(&[]),
+            max_q: q.max().double_value(&[]),
+            td_error_mean: td_error.mean(Float).double_value(&[]),
+            td_error_std: td_error.std(true).double_value(&[]),
+            td_error_max: td_error.max().double_value(&[]),
+        })
+    }
+
+    fn set_lr(&mut self, actor_lr: f64, critic_lr: f64) {
+        self.actor.optimizer_mut().set_lr(actor_lr);
+        self.critic.optimizer_mut().set_lr(critic_lr);
     }
 
-    pub fn save(&mut self) {
+    fn save(&mut self) {
         self.actor.save();
     }
+
+    fn save_to(&mut self, path: &str) {
+        self.actor.save_to(path);
+    }
 }
@@ -1,10 +1,38 @@
-use crate::utils::functions::create_network;
-use tch::nn::{Adam, Optimizer, OptimizerConfig, Sequential, VarStore};
-use tch::{Device, Tensor};
+use crate::model::encoder::ConvEncoder;
+use crate::model::spec::NetworkSpec;
+use crate::utils::functions::{create_hidden, create_network};
+use tch::kind::{FLOAT_CPU, FLOAT_CUDA};
+use tch::nn::{Adam, ModuleT, Optimizer, OptimizerConfig, SequentialT, VarStore};
+use tch::Kind::Float;
+use tch::{Cuda, Device, Tensor};
 
+/// # Critic output head
+///
+/// - `Scalar`: a single Q value for the concatenated actions and observations
+/// - `Dueling`: a value and one advantage per discrete action computed from the observations only,
+///   Q(s, a) is the expectation of the discrete Q values under softmax(a)
+/// - `Categorical`: a distribution over `atoms` returns evenly spaced in [`v_min`, `v_max`] (C51)
+#[derive(Clone, Copy, Debug)]
+pub enum CriticHead {
+    Scalar,
+    Dueling,
+    Categorical { atoms: i64, v_min: f64, v_max: f64 },
+}
+
+/// # Critic
+///
+/// - `encoder`: optional encoder of the grid observation
+/// - `obs_network`: hidden layers applied to the observations only
+/// - `network`: layers applied to the actions concatenated with the `obs_network` output
+/// - `support`: returns of the atoms of the categorical head
 pub struct Critic {
     vs: VarStore,
-    network: Sequential,
+    encoder: Option<ConvEncoder>,
+    obs_network: SequentialT,
+    network: SequentialT,
+    head: CriticHead,
+    support: Option<Tensor>,
+    action_space: i64,
     device: Device,
     optimizer: Optimizer,
 }
@@ -14,21 +42,54 @@ impl Critic {
         observation_space: usize,
         action_space: usize,
         lr: f64,
-        hidden_layers: &[i64],
+        spec: &NetworkSpec,
+        head: CriticHead,
     ) -> Self {
         let device = Device::cuda_if_available();
         let vs = VarStore::new(device);
         let optimizer = Adam::default().build(&vs, lr).unwrap();
         let p = &vs.root();
-        let network = create_network(
-            p,
-            observation_space as i64,
-            action_space as i64,
-            hidden_layers,
-            true,
-        );
+        let (obs_layers, layers) = match head {
+            // the dueling head never sees the actions
+            CriticHead::Dueling => spec.layers.split_at(spec.layers.len()),
+            _ => spec.layers.split_at(spec.action_layer),
+        };
+        let encoder = spec
+            .encoder
+            .map(|encoder| ConvEncoder::new(&(p / "encoder"), observation_space as i64, &encoder));
+        let obs_features = encoder
+            .as_ref()
+            .map_or(observation_space as i64, |encoder| encoder.output_dim());
+        let obs_network = create_hidden(&(p / "obs"), obs_features, obs_layers, spec);
+        let features = obs_layers.last().copied().unwrap_or(obs_features);
+        let (input, output) = match head {
+            CriticHead::Scalar => (features + action_space as i64, 1),
+            CriticHead::Dueling => (features, 1 + action_space as i64),
+            CriticHead::Categorical { atoms, .. } => (features + action_space as i64, atoms),
+        };
+        let network = create_network(p, input, output, &NetworkSpec { layers, ..*spec });
+        let support = match head {
+            CriticHead::Categorical {
+                atoms,
+                v_min,
+                v_max,
+            } => {
+                let mode = if Cuda::is_available() {
+                    FLOAT_CUDA
+                } else {
+                    FLOAT_CPU
+                };
+                Some(Tensor::linspace(v_min, v_max, atoms, mode))
+            }
+            _ => None,
+        };
         Self {
+            encoder,
+            obs_network,
             network,
+            head,
+            support,
+            action_space: action_space as i64,
             device: p.device(),
             vs,
             optimizer,
@@ -36,10 +97,56 @@ impl Critic {
     }
 
     pub fn forward(&self, obs: &Tensor, actions: &Tensor) -> Tensor {
-        let xs = Tensor::cat(&[actions.copy(), obs.copy()], 1);
-        xs.to_device(self.device).apply(&self.network)
+        self.forward_t(obs, actions, false)
+    }
+
+    // Q value of every observation-actions pair, shape [batch, 1]
+    pub fn forward_t(&self, obs: &Tensor, actions: &Tensor, train: bool) -> Tensor {
+        match self.head {
+            CriticHead::Scalar => self.logits_t(obs, actions, train),
+            CriticHead::Dueling => {
+                let out = self.logits_t(obs, actions, train);
+                let value = out.narrow(1, 0, 1);
+                let advantage = out.narrow(1, 1, self.action_space);
+                let q = value + &advantage - advantage.mean_dim([1].as_slice(), true, Float);
+                let weights = actions.to_device(self.device).softmax(-1, Float);
+                (weights * q).sum_dim_intlist([1].as_slice(), true, Float)
+            }
+            CriticHead::Categorical { .. } => {
+                let probs = self.log_probs_t(obs, actions, train).exp();
+                (probs * self.support.as_ref().unwrap()).sum_dim_intlist(
+                    [1].as_slice(),
+                    true,
+                    Float,
+                )
+            }
+        }
+    }
+
+    // log probabilities of the atoms of the categorical head, shape [batch, atoms]
+    pub fn log_probs_t(&self, obs: &Tensor, actions: &Tensor, train: bool) -> Tensor {
+        self.logits_t(obs, actions, train).log_softmax(-1, Float)
+    }
+
+    fn logits_t(&self, obs: &Tensor, actions: &Tensor, train: bool) -> Tensor {
+        let mut obs = obs.to_device(self.device);
+        if let Some(encoder) = &self.encoder {
+            obs = encoder.forward_t(&obs, train);
+        }
+        let features = self.obs_network.forward_t(&obs, train);
+        let xs = match self.head {
+            CriticHead::Dueling => features,
+            _ => Tensor::cat(&[actions.to_device(self.device), features], 1),
+        };
+        self.network.forward_t(&xs, train)
     }
 
+    pub fn head(&self) -> CriticHead {
+        self.head
+    }
+    pub fn support(&self) -> Option<&Tensor> {
+        self.support.as_ref()
+    }
     pub fn optimizer_mut(&mut self) -> &mut Optimizer {
         &mut self.optimizer
     }
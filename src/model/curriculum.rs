@@ -0,0 +1,89 @@
+use crate::world::generator::{MapParams, ProceduralGenerator};
+use crate::world::WorldSource;
+use std::collections::VecDeque;
+
+/// # Curriculum stage
+///
+/// - `Map`: map of the maps folder
+/// - `Generated`: procedural worlds with the given parameters, a new one every episode
+#[derive(Clone, Copy, Debug)]
+pub enum Stage {
+    Map(&'static str),
+    Generated(MapParams),
+}
+
+impl Stage {
+    pub fn name(&self) -> String {
+        match self {
+            Stage::Map(map) => map.to_string(),
+            Stage::Generated(params) => format!("generated_{}", params.size),
+        }
+    }
+}
+
+/// # Curriculum
+///
+/// Moves to the next stage when the success rate of the last `window` training episodes
+/// reaches `threshold`
+///
+/// - `stages`: stages from the easiest
+/// - `stage`: index of the current stage
+/// - `outcomes`: success of the last training episodes of the current stage
+pub struct Curriculum {
+    stages: &'static [Stage],
+    window: usize,
+    threshold: f64,
+    stage: usize,
+    outcomes: VecDeque<bool>,
+}
+
+impl Curriculum {
+    pub fn new(stages: &'static [Stage], window: usize, threshold: f64) -> Self {
+        assert!(!stages.is_empty(), "The curriculum has no stages");
+        Self {
+            stages,
+            window,
+            threshold,
+            stage: 0,
+            outcomes: VecDeque::new(),
+        }
+    }
+
+    pub fn stage(&self) -> usize {
+        self.stage
+    }
+
+    pub fn is_last(&self) -> bool {
+        self.stage + 1 == self.stages.len()
+    }
+
+    pub fn name(&self) -> String {
+        self.stages[self.stage].name()
+    }
+
+    // worlds of the current stage
+    pub fn source(&self, seed: u64) -> WorldSource {
+        match self.stages[self.stage] {
+            Stage::Map(map) => WorldSource::map(map),
+            Stage::Generated(params) => {
+                WorldSource::Procedural(ProceduralGenerator::new(MapParams { seed, ..params }))
+            }
+        }
+    }
+
+    // register a training episode, returns true if the curriculum moved to the next stage
+    pub fn update(&mut self, success: bool) -> bool {
+        self.outcomes.push_back(success);
+        if self.outcomes.len() > self.window {
+            self.outcomes.pop_front();
+        }
+        let rate =
+            self.outcomes.iter().filter(|&&success| success).count() as f64 / self.window as f64;
+        if self.is_last() || self.outcomes.len() < self.window || rate < self.threshold {
+            return false;
+        }
+        self.stage += 1;
+        self.outcomes.clear();
+        true
+    }
+}
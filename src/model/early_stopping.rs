@@ -0,0 +1,46 @@
+use crate::utils::stats::EvalSummary;
+
+/// # Early stopping
+///
+/// - `patience`: evaluations without improvement of the mean return before stopping
+/// - `success_rate`: success rate that is considered good enough to stop
+/// - `min_episodes`: episodes an evaluation needs for its success rate to be trusted
+/// - `best`: best mean return seen so far
+/// - `evals_without_improvement`: evaluations since the last improvement
+pub struct EarlyStopping {
+    patience: Option<usize>,
+    success_rate: Option<f64>,
+    min_episodes: usize,
+    best: f64,
+    evals_without_improvement: usize,
+}
+
+impl EarlyStopping {
+    pub fn new(patience: Option<usize>, success_rate: Option<f64>, min_episodes: usize) -> Self {
+        Self {
+            patience,
+            success_rate,
+            min_episodes,
+            best: f64::MIN,
+            evals_without_improvement: 0,
+        }
+    }
+
+    // register an evaluation, returns true if training should stop
+    pub fn update(&mut self, summary: &EvalSummary) -> bool {
+        if summary.mean_return > self.best {
+            self.best = summary.mean_return;
+            self.evals_without_improvement = 0;
+        } else {
+            self.evals_without_improvement += 1;
+        }
+        let no_improvement = self
+            .patience
+            .map_or(false, |patience| self.evals_without_improvement >= patience);
+        let solved = summary.episodes >= self.min_episodes
+            && self
+                .success_rate
+                .map_or(false, |success_rate| summary.success_rate >= success_rate);
+        no_improvement || solved
+    }
+}
@@ -0,0 +1,92 @@
+use crate::utils::consts::{GRID_CHANNELS, GRID_OBSERVATION};
+use crate::utils::functions::flat_size;
+use tch::nn::{conv2d, linear, seq_t, ConvConfig, ModuleT, Path, SequentialT};
+use tch::Tensor;
+
+/// # Convolutional encoder specification
+///
+/// - `channels`: output channels of every convolution
+/// - `kernel`: kernel size of the convolutions, padded to keep the grid size
+/// - `features`: size of the linear layer after the convolutions
+#[derive(Clone, Copy, Debug)]
+pub struct EncoderSpec {
+    pub channels: &'static [i64],
+    pub kernel: i64,
+    pub features: i64,
+}
+
+/// # Convolutional encoder
+///
+/// Splits the observations in the flat observations and the egocentric grid,
+/// the output is the flat observations followed by the features of the grid
+///
+/// - `conv`: convolutions, flattening and linear layer applied to the grid
+/// - `flat`: size of the flat observations
+/// - `size`: side of the grid
+/// - `features`: number of grid features
+pub struct ConvEncoder {
+    conv: SequentialT,
+    flat: i64,
+    size: i64,
+    features: i64,
+}
+
+impl ConvEncoder {
+    pub fn new(p: &Path, observation_space: i64, spec: &EncoderSpec) -> Self {
+        let size =
+            GRID_OBSERVATION.expect("The convolutional encoder requires GRID_OBSERVATION") as i64;
+        let flat = flat_size();
+        assert_eq!(
+            observation_space - flat,
+            size * size * GRID_CHANNELS as i64,
+            "The observations don't end with a grid of side {}",
+            size
+        );
+        let mut conv = seq_t();
+        let mut in_channels = GRID_CHANNELS as i64;
+        for (i, &out_channels) in spec.channels.iter().enumerate() {
+            conv = conv
+                .add(conv2d(
+                    p / format!("conv{}", i),
+                    in_channels,
+                    out_channels,
+                    spec.kernel,
+                    ConvConfig {
+                        padding: spec.kernel / 2,
+                        ..Default::default()
+                    },
+                ))
+                .add_fn(|xs| xs.relu());
+            in_channels = out_channels;
+        }
+        let conv = conv
+            .add_fn(|xs| xs.flatten(1, -1))
+            .add(linear(
+                p / "features",
+                in_channels * size * size,
+                spec.features,
+                Default::default(),
+            ))
+            .add_fn(|xs| xs.relu());
+        Self {
+            conv,
+            flat,
+            size,
+            features: spec.features,
+        }
+    }
+
+    pub fn output_dim(&self) -> i64 {
+        self.flat + self.features
+    }
+
+    // `obs` has shape [batch, observation_space]
+    pub fn forward_t(&self, obs: &Tensor, train: bool) -> Tensor {
+        let channels = GRID_CHANNELS as i64;
+        let flat = obs.narrow(1, 0, self.flat);
+        let grid = obs
+            .narrow(1, self.flat, channels * self.size * self.size)
+            .reshape([-1, channels, self.size, self.size]);
+        Tensor::cat(&[flat, self.conv.forward_t(&grid, train)], 1)
+    }
+}
@@ -0,0 +1,7 @@
+/// # Episodic
+///
+/// Policy holding state across the steps of an episode,
+/// `reset` is called by `new_episode` every time the environment is reset
+pub trait Episodic {
+    fn reset(&mut self);
+}
@@ -0,0 +1,18 @@
+use crate::model::episodic::Episodic;
+use crate::model::metrics::TrainMetrics;
+use tch::Tensor;
+
+/// # Learner
+///
+/// Interface the training loop uses to drive an agent
+pub trait Learner: Episodic {
+    // actions with exploration noise when training
+    fn actions(&mut self, obs: &Tensor) -> Tensor;
+    // actions without exploration noise
+    fn greedy_actions(&mut self, obs: &Tensor) -> Tensor;
+    fn remember(&mut self, obs: &Tensor, actions: &Tensor, reward: &Tensor, next_obs: &Tensor);
+    fn train(&mut self, batch_size: usize) -> Option<TrainMetrics>;
+    fn set_lr(&mut self, actor_lr: f64, critic_lr: f64);
+    fn save(&mut self);
+    fn save_to(&mut self, path: &str);
+}
@@ -0,0 +1,87 @@
+use crate::model::episodic::Episodic;
+use crate::model::manifest::check_model;
+use crate::model::recurrent::RnnKind;
+use crate::utils::consts::{RECURRENT, RNN_HIDDEN};
+use tch::Kind::Float;
+use tch::{CModule, Device, IValue, Tensor};
+
+/// # Loaded actor
+///
+/// TorchScript actor loaded for inference, a recurrent one gets its hidden state
+/// as extra inputs and outputs
+///
+/// - `model`: traced actor
+/// - `recurrent`: cell and hidden size of a recurrent actor, from its manifest
+///   or from the current configuration if it has none
+/// - `hidden`: hidden state carried between the steps, empty for a feed-forward actor
+/// - `device`: device of the model and of the hidden state
+pub struct LoadedActor {
+    model: CModule,
+    recurrent: Option<(RnnKind, i64)>,
+    hidden: Vec<Tensor>,
+    device: Device,
+}
+
+impl LoadedActor {
+    // actor checked against its manifest
+    pub fn load(model_path: &str) -> Self {
+        let recurrent = match check_model(model_path) {
+            Some(manifest) => manifest.recurrent(),
+            None => RECURRENT.map(|kind| (kind, RNN_HIDDEN)),
+        };
+        let device = Device::cuda_if_available();
+        let mut model = CModule::load_on_device(model_path, device).unwrap();
+        model.set_eval();
+        let mut actor = Self {
+            model,
+            recurrent,
+            hidden: vec![],
+            device,
+        };
+        actor.reset();
+        actor
+    }
+
+    // outputs for a single observation, advancing the hidden state
+    pub fn forward(&mut self, obs: &Tensor) -> Tensor {
+        let obs = obs.to_device(self.device);
+        if self.recurrent.is_none() {
+            return obs.apply(&self.model);
+        }
+        let mut inputs = vec![IValue::Tensor(obs)];
+        inputs.extend(
+            self.hidden
+                .iter()
+                .map(|h| IValue::Tensor(h.shallow_clone())),
+        );
+        let IValue::Tuple(outputs) = self.model.forward_is(&inputs).unwrap() else {
+            panic!("The recurrent actor didn't return its hidden state");
+        };
+        let mut outputs = outputs.into_iter().map(|output| match output {
+            IValue::Tensor(tensor) => tensor,
+            _ => panic!("The recurrent actor returned a value that isn't a tensor"),
+        });
+        let actions = outputs.next().unwrap();
+        self.hidden = outputs.collect();
+        actions
+    }
+
+    pub fn is_recurrent(&self) -> bool {
+        self.recurrent.is_some()
+    }
+}
+
+impl Episodic for LoadedActor {
+    fn reset(&mut self) {
+        self.hidden = match self.recurrent {
+            None => vec![],
+            Some((kind, size)) => {
+                let zeros = || Tensor::zeros([1, 1, size], (Float, self.device));
+                match kind {
+                    RnnKind::Lstm => vec![zeros(), zeros()],
+                    RnnKind::Gru => vec![zeros()],
+                }
+            }
+        };
+    }
+}
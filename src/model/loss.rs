@@ -0,0 +1,65 @@
+use tch::Kind::{Float, Int64};
+use tch::{Reduction, Tensor};
+
+/// # Critic loss
+///
+/// - `Mse`: mean squared TD error
+/// - `Huber`: squared below `delta`, linear above, less sensitive to the large illegal action penalties
+#[derive(Clone, Copy, Debug)]
+pub enum CriticLoss {
+    Mse,
+    Huber { delta: f64 },
+}
+
+impl CriticLoss {
+    pub fn compute(&self, q: &Tensor, q_target: &Tensor) -> Tensor {
+        match *self {
+            CriticLoss::Mse => {
+                let diff = q_target - q;
+                (&diff * &diff).mean(Float)
+            }
+            CriticLoss::Huber { delta } => q.huber_loss(q_target, Reduction::Mean, delta),
+        }
+    }
+
+    // loss averaged over the elements where `mask` is 1
+    pub fn compute_masked(&self, q: &Tensor, q_target: &Tensor, mask: &Tensor) -> Tensor {
+        let losses = match *self {
+            CriticLoss::Mse => {
+                let diff = q_target - q;
+                &diff * &diff
+            }
+            CriticLoss::Huber { delta } => q.huber_loss(q_target, Reduction::None, delta),
+        };
+        (losses * mask).sum(Float) / mask.sum(Float)
+    }
+}
+
+// projection of the distribution of r + gamma * z onto the support of the categorical head (C51),
+// `next_probs` are the probabilities of the atoms for the next observations
+pub fn categorical_projection(
+    next_probs: &Tensor,
+    rewards: &Tensor,
+    gamma: f64,
+    support: &Tensor,
+    v_min: f64,
+    v_max: f64,
+) -> Tensor {
+    let atoms = support.size()[0];
+    let delta_z = (v_max - v_min) / (atoms - 1) as f64;
+    let tz = (rewards + gamma * support.unsqueeze(0)).clamp(v_min, v_max);
+    let b = (tz - v_min) / delta_z;
+    let l = b.floor();
+    let u = b.ceil();
+    // if b lands exactly on an atom move one of the bounds so that the mass isn't lost
+    let l = &l - l.eq_tensor(&u).logical_and(&u.gt(0.)).to_kind(Float);
+    let u = &u
+        + l.eq_tensor(&u)
+            .logical_and(&l.lt((atoms - 1) as f64))
+            .to_kind(Float);
+
+    let mut target = next_probs.zeros_like();
+    let _ = target.scatter_add_(1, &l.to_kind(Int64), &(next_probs * (&u - &b)));
+    let _ = target.scatter_add_(1, &u.to_kind(Int64), &(next_probs * (&b - &l)));
+    target
+}
@@ -0,0 +1,248 @@
+use crate::model::loaded_actor::LoadedActor;
+use crate::model::recurrent::RnnKind;
+use crate::utils::consts::{
+    ACTOR_GRAD_CLIP, ACTOR_SPEC, BATCH, BURN_IN, CRITIC_GRAD_CLIP, CRITIC_HEAD, CRITIC_LOSS,
+    CRITIC_SPEC, CURRICULUM, CURRICULUM_THRESHOLD, CURRICULUM_WINDOW, DOMAIN_RANDOMIZATION,
+    EVENT_REWARDS, GAMMA, LR_A, LR_A_SCHEDULER, LR_C, LR_C_SCHEDULER, MAX_EP, MEM_DIM, MU,
+    N_ACTIONS, RECURRENT, RESET_OPTIONS, RNN_HIDDEN, SEQ_LEN, SIGMA, TASK, TAU, THETA,
+    TRAIN_ITERATIONS,
+};
+use crate::utils::functions::{action_name, observation_layout, observation_size};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::process::Command;
+
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// # Space specification
+///
+/// - `size`: number of features or actions
+/// - `layout`: name of every feature or action in order
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SpaceSpec {
+    pub size: usize,
+    pub layout: Vec<String>,
+}
+
+/// # Model manifest
+///
+/// Saved next to every model as `<model>.manifest.json`
+///
+/// - `observation`: observations the model was trained on
+/// - `action`: actions the model outputs
+/// - `algorithm`: training algorithm
+/// - `hyperparameters`: training configuration, values formatted as text
+/// - `git_commit`: commit of the code that trained the model, `-dirty` if it had local changes
+/// - `map`: training map
+/// - `episode`: training episode the model was saved at
+/// - `eval_score`: mean evaluation return when the model was saved
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Manifest {
+    pub format_version: u32,
+    pub observation: SpaceSpec,
+    pub action: SpaceSpec,
+    pub algorithm: String,
+    pub hyperparameters: BTreeMap<String, String>,
+    pub git_commit: Option<String>,
+    pub map: String,
+    pub episode: usize,
+    pub eval_score: f64,
+}
+
+impl Manifest {
+    // manifest of a model trained with the current configuration
+    pub fn new(map: &str, episode: usize, eval_score: f64) -> Self {
+        Self {
+            format_version: MANIFEST_VERSION,
+            observation: current_observation(),
+            action: current_action(),
+            algorithm: algorithm(),
+            hyperparameters: hyperparameters(),
+            git_commit: git_commit(),
+            map: map.to_string(),
+            episode,
+            eval_score,
+        }
+    }
+
+    pub fn save(&self, path: &str) {
+        serde_json::to_writer_pretty(File::create(path).unwrap(), self).unwrap();
+    }
+
+    pub fn load(path: &str) -> Option<Self> {
+        File::open(path)
+            .ok()
+            .map(|file| serde_json::from_reader(file).unwrap())
+    }
+
+    // differences that make the model unusable with the current observations and actions
+    pub fn incompatibilities(&self) -> Vec<String> {
+        let mut problems = vec![];
+        let observation = current_observation();
+        if self.observation.size != observation.size {
+            problems.push(format!(
+                "{} observations instead of {}",
+                self.observation.size, observation.size
+            ));
+        } else if self.observation.layout != observation.layout {
+            problems.push("different observation layout".to_string());
+        }
+        let action = current_action();
+        if self.action.size != action.size {
+            problems.push(format!(
+                "{} actions instead of {}",
+                self.action.size, action.size
+            ));
+        } else if self.action.layout != action.layout {
+            problems.push("different action layout".to_string());
+        }
+        if self.recurrent_kind().is_some() && self.recurrent().is_none() {
+            problems.push("recurrent actor without a valid rnn_hidden".to_string());
+        }
+        problems
+    }
+
+    // cell and hidden size of a recurrent actor, None for a feed-forward one
+    // or if the hidden size is missing
+    pub fn recurrent(&self) -> Option<(RnnKind, i64)> {
+        let hidden = self
+            .hyperparameters
+            .get("rnn_hidden")
+            .and_then(|value| value.parse().ok())?;
+        Some((self.recurrent_kind()?, hidden))
+    }
+
+    fn recurrent_kind(&self) -> Option<RnnKind> {
+        if self.algorithm.ends_with("(Lstm)") {
+            Some(RnnKind::Lstm)
+        } else if self.algorithm.ends_with("(Gru)") {
+            Some(RnnKind::Gru)
+        } else {
+            None
+        }
+    }
+}
+
+// path of the manifest of a saved model
+pub fn manifest_path(model_path: &str) -> String {
+    format!("{}.manifest.json", model_path.trim_end_matches(".pt"))
+}
+
+// panics if the manifest of the model doesn't match the current observations and actions,
+// models saved without a manifest are accepted with a warning
+pub fn check_model(model_path: &str) -> Option<Manifest> {
+    let Some(manifest) = Manifest::load(&manifest_path(model_path)) else {
+        println!(
+            "Warning: {} has no manifest, it can't be checked",
+            model_path
+        );
+        return None;
+    };
+    if manifest.format_version != MANIFEST_VERSION {
+        println!(
+            "Warning: {} has a manifest of version {}, expected {}",
+            model_path, manifest.format_version, MANIFEST_VERSION
+        );
+    }
+    let problems = manifest.incompatibilities();
+    assert!(
+        problems.is_empty(),
+        "{} is incompatible with the current configuration: {}",
+        model_path,
+        problems.join(", ")
+    );
+    Some(manifest)
+}
+
+// TorchScript actor checked against its manifest, ready for inference
+pub fn load_model(model_path: &str) -> LoadedActor {
+    LoadedActor::load(model_path)
+}
+
+fn current_observation() -> SpaceSpec {
+    SpaceSpec {
+        size: observation_size() as usize,
+        layout: observation_layout(),
+    }
+}
+
+fn current_action() -> SpaceSpec {
+    SpaceSpec {
+        size: N_ACTIONS as usize,
+        layout: (0..N_ACTIONS).map(action_name).collect(),
+    }
+}
+
+// name of the training algorithm of the current configuration
+pub fn algorithm() -> String {
+    match RECURRENT {
+        None => "DDPG".to_string(),
+        Some(kind) => format!("Recurrent DDPG ({:?})", kind),
+    }
+}
+
+// training configuration, values formatted as text
+pub fn hyperparameters() -> BTreeMap<String, String> {
+    let mut hyperparameters = BTreeMap::new();
+    let mut set = |key: &str, value: String| {
+        hyperparameters.insert(key.to_string(), value);
+    };
+    set("lr_actor", LR_A.to_string());
+    set("lr_critic", LR_C.to_string());
+    set("lr_actor_scheduler", format!("{:?}", LR_A_SCHEDULER));
+    set("lr_critic_scheduler", format!("{:?}", LR_C_SCHEDULER));
+    set("gamma", GAMMA.to_string());
+    set("tau", TAU.to_string());
+    set("batch", BATCH.to_string());
+    set("memory", MEM_DIM.to_string());
+    set("train_iterations", TRAIN_ITERATIONS.to_string());
+    set("max_steps", MAX_EP.to_string());
+    set(
+        "noise",
+        format!("theta {} sigma {} mu {}", THETA, SIGMA, MU),
+    );
+    set("actor_spec", format!("{:?}", ACTOR_SPEC));
+    set("critic_spec", format!("{:?}", CRITIC_SPEC));
+    set("critic_head", format!("{:?}", CRITIC_HEAD));
+    set("critic_loss", format!("{:?}", CRITIC_LOSS));
+    set("actor_grad_clip", format!("{:?}", ACTOR_GRAD_CLIP));
+    set("critic_grad_clip", format!("{:?}", CRITIC_GRAD_CLIP));
+    set("task", format!("{:?}", TASK));
+    set(
+        "domain_randomization",
+        format!("{:?}", DOMAIN_RANDOMIZATION),
+    );
+    set("reset_options", format!("{:?}", RESET_OPTIONS));
+    set("event_rewards", format!("{:?}", EVENT_REWARDS));
+    if let Some(stages) = CURRICULUM {
+        set("curriculum", format!("{:?}", stages));
+        set("curriculum_window", CURRICULUM_WINDOW.to_string());
+        set("curriculum_threshold", CURRICULUM_THRESHOLD.to_string());
+    }
+    if RECURRENT.is_some() {
+        set("rnn_hidden", RNN_HIDDEN.to_string());
+        set("burn_in", BURN_IN.to_string());
+        set("seq_len", SEQ_LEN.to_string());
+    }
+    hyperparameters
+}
+
+// current commit, with a `-dirty` suffix if there are local changes
+pub fn git_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let dirty = Command::new("git")
+        .args(["diff", "--quiet", "HEAD"])
+        .status()
+        .map_or(false, |status| !status.success());
+    Some(if dirty {
+        format!("{}-dirty", commit)
+    } else {
+        commit
+    })
+}
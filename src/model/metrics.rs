@@ -0,0 +1,39 @@
+/// # Training metrics
+///
+/// Diagnostics of a single `Agent::train` call,
+/// the gradient norms are measured before clipping
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TrainMetrics {
+    pub critic_loss: f64,
+    pub actor_loss: f64,
+    pub critic_grad_norm: f64,
+    pub actor_grad_norm: f64,
+    pub mean_q: f64,
+    pub max_q: f64,
+    pub td_error_mean: f64,
+    pub td_error_std: f64,
+    pub td_error_max: f64,
+}
+
+impl TrainMetrics {
+    // average of the metrics of many train calls, max values are kept as maximums
+    pub fn mean(metrics: &[TrainMetrics]) -> Option<Self> {
+        if metrics.is_empty() {
+            return None;
+        }
+        let n = metrics.len() as f64;
+        let avg = |f: fn(&TrainMetrics) -> f64| metrics.iter().map(f).sum::<f64>() / n;
+        let max = |f: fn(&TrainMetrics) -> f64| metrics.iter().map(f).fold(f64::MIN, f64::max);
+        Some(Self {
+            critic_loss: avg(|m| m.critic_loss),
+            actor_loss: avg(|m| m.actor_loss),
+            critic_grad_norm: avg(|m| m.critic_grad_norm),
+            actor_grad_norm: avg(|m| m.actor_grad_norm),
+            mean_q: avg(|m| m.mean_q),
+            max_q: max(|m| m.max_q),
+            td_error_mean: avg(|m| m.td_error_mean),
+            td_error_std: avg(|m| m.td_error_std),
+            td_error_max: max(|m| m.td_error_max),
+        })
+    }
+}
@@ -1,10 +1,45 @@
 mod actor;
 mod agent;
 mod critic;
+mod curriculum;
+mod early_stopping;
+mod encoder;
+mod episodic;
+mod learner;
+mod loaded_actor;
+mod loss;
+mod manifest;
 mod memory;
+mod metrics;
 mod noise;
+mod recurrent;
+mod recurrent_actor;
+mod recurrent_agent;
+mod recurrent_critic;
+mod scheduler;
+mod sequence_memory;
+mod spec;
 pub use actor::Actor;
 pub use agent::Agent;
-pub use critic::Critic;
+pub use critic::{Critic, CriticHead};
+pub use curriculum::{Curriculum, Stage};
+pub use early_stopping::EarlyStopping;
+pub use encoder::{ConvEncoder, EncoderSpec};
+pub use episodic::Episodic;
+pub use learner::Learner;
+pub use loaded_actor::LoadedActor;
+pub use loss::CriticLoss;
+pub use manifest::{
+    algorithm, check_model, git_commit, hyperparameters, load_model, manifest_path, Manifest,
+    SpaceSpec,
+};
 pub use memory::ReplayMemory;
+pub use metrics::TrainMetrics;
 pub use noise::Noise;
+pub use recurrent::RnnKind;
+pub use recurrent_actor::RecurrentActor;
+pub use recurrent_agent::RecurrentAgent;
+pub use recurrent_critic::RecurrentCritic;
+pub use scheduler::LrScheduler;
+pub use sequence_memory::SequenceMemory;
+pub use spec::{Activation, InitScheme, NetworkSpec, Norm};
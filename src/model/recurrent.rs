@@ -0,0 +1,119 @@
+use crate::model::spec::NetworkSpec;
+use crate::utils::functions::create_network;
+use tch::nn::{gru, lstm, GRUState, LSTMState, ModuleT, Path, SequentialT, GRU, LSTM, RNN};
+use tch::Tensor;
+
+/// # Recurrent cell type
+#[derive(Clone, Copy, Debug)]
+pub enum RnnKind {
+    Lstm,
+    Gru,
+}
+
+/// # Recurrent hidden state
+pub enum Hidden {
+    Lstm(LSTMState),
+    Gru(GRUState),
+}
+
+impl Hidden {
+    // output of the last layer, shape [batch, hidden]
+    pub fn h(&self) -> Tensor {
+        match self {
+            Hidden::Lstm(state) => state.h(),
+            Hidden::Gru(state) => state.h(),
+        }
+    }
+
+    // same state cut from the computation graph
+    pub fn detach(&self) -> Self {
+        match self {
+            Hidden::Lstm(LSTMState((h, c))) => Hidden::Lstm(LSTMState((h.detach(), c.detach()))),
+            Hidden::Gru(GRUState(h)) => Hidden::Gru(GRUState(h.detach())),
+        }
+    }
+
+    // flat representation used as input and output of the traced modules
+    pub fn tensors(&self) -> Vec<Tensor> {
+        match self {
+            Hidden::Lstm(LSTMState((h, c))) => vec![h.shallow_clone(), c.shallow_clone()],
+            Hidden::Gru(GRUState(h)) => vec![h.shallow_clone()],
+        }
+    }
+
+    pub fn from_tensors(kind: RnnKind, tensors: &[Tensor]) -> Self {
+        match kind {
+            RnnKind::Lstm => Hidden::Lstm(LSTMState((
+                tensors[0].shallow_clone(),
+                tensors[1].shallow_clone(),
+            ))),
+            RnnKind::Gru => Hidden::Gru(GRUState(tensors[0].shallow_clone())),
+        }
+    }
+}
+
+enum Cell {
+    Lstm(LSTM),
+    Gru(GRU),
+}
+
+/// # Recurrent network
+///
+/// - `cell`: recurrent layer the inputs go through first
+/// - `head`: network applied to the output of the recurrent layer,
+///   batch norm can't be used since it gets sequences as input
+pub struct RecurrentNet {
+    cell: Cell,
+    head: SequentialT,
+}
+
+impl RecurrentNet {
+    pub fn new(
+        p: &Path,
+        input: i64,
+        hidden: i64,
+        output: i64,
+        kind: RnnKind,
+        spec: &NetworkSpec,
+    ) -> Self {
+        let cell = match kind {
+            RnnKind::Lstm => Cell::Lstm(lstm(p / "rnn", input, hidden, Default::default())),
+            RnnKind::Gru => Cell::Gru(gru(p / "rnn", input, hidden, Default::default())),
+        };
+        let head = create_network(&(p / "head"), hidden, output, spec);
+        Self { cell, head }
+    }
+
+    pub fn zero_state(&self, batch: i64) -> Hidden {
+        match &self.cell {
+            Cell::Lstm(cell) => Hidden::Lstm(cell.zero_state(batch)),
+            Cell::Gru(cell) => Hidden::Gru(cell.zero_state(batch)),
+        }
+    }
+
+    // single step, `xs` has shape [batch, input]
+    pub fn step(&self, xs: &Tensor, hidden: &Hidden, train: bool) -> (Tensor, Hidden) {
+        let hidden = match (&self.cell, hidden) {
+            (Cell::Lstm(cell), Hidden::Lstm(state)) => Hidden::Lstm(cell.step(xs, state)),
+            (Cell::Gru(cell), Hidden::Gru(state)) => Hidden::Gru(cell.step(xs, state)),
+            _ => panic!("The hidden state doesn't match the recurrent cell"),
+        };
+        (self.head.forward_t(&hidden.h(), train), hidden)
+    }
+
+    // whole sequence, `xs` has shape [batch, seq, input]
+    pub fn seq(&self, xs: &Tensor, hidden: &Hidden, train: bool) -> (Tensor, Hidden) {
+        let (out, hidden) = match (&self.cell, hidden) {
+            (Cell::Lstm(cell), Hidden::Lstm(state)) => {
+                let (out, state) = cell.seq_init(xs, state);
+                (out, Hidden::Lstm(state))
+            }
+            (Cell::Gru(cell), Hidden::Gru(state)) => {
+                let (out, state) = cell.seq_init(xs, state);
+                (out, Hidden::Gru(state))
+            }
+            _ => panic!("The hidden state doesn't match the recurrent cell"),
+        };
+        (self.head.forward_t(&out, train), hidden)
+    }
+}
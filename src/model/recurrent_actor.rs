@@ -0,0 +1,129 @@
+use crate::model::recurrent::{Hidden, RecurrentNet, RnnKind};
+use crate::model::spec::NetworkSpec;
+use tch::kind::{FLOAT_CPU, FLOAT_CUDA};
+use tch::nn::{Adam, Optimizer, OptimizerConfig, VarStore};
+use tch::{no_grad, CModule, Cuda, Device, Tensor};
+
+/// # Recurrent actor
+///
+/// Keeps the hidden state across the steps of an episode,
+/// `reset` has to be called at the start of every episode
+pub struct RecurrentActor {
+    save_path: String,
+    vs: VarStore,
+    network: RecurrentNet,
+    hidden: Hidden,
+    kind: RnnKind,
+    device: Device,
+    observation_space: usize,
+    action_space: usize,
+    optimizer: Optimizer,
+}
+
+impl RecurrentActor {
+    pub fn new(
+        observation_space: usize,
+        action_space: usize,
+        lr: f64,
+        hidden_size: i64,
+        kind: RnnKind,
+        spec: &NetworkSpec,
+        save_path: String,
+    ) -> Self {
+        let device = Device::cuda_if_available();
+        let vs = VarStore::new(device);
+        let optimizer = Adam::default().build(&vs, lr).unwrap();
+        let p = &vs.root();
+        let network = RecurrentNet::new(
+            p,
+            observation_space as i64,
+            hidden_size,
+            action_space as i64,
+            kind,
+            spec,
+        );
+        let hidden = network.zero_state(1);
+        Self {
+            save_path,
+            device: p.device(),
+            network,
+            hidden,
+            kind,
+            observation_space,
+            action_space,
+            vs,
+            optimizer,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.hidden = self.network.zero_state(1);
+    }
+
+    // actions for a single observation, advancing the hidden state
+    pub fn act(&mut self, obs: &Tensor) -> Tensor {
+        let obs = obs.to_device(self.device).unsqueeze(0);
+        let (actions, hidden) = no_grad(|| self.network.step(&obs, &self.hidden, false));
+        self.hidden = hidden;
+        actions.squeeze_dim(0)
+    }
+
+    // actions for a batch of sequences of observations, shape [batch, seq, actions]
+    pub fn forward_seq(&self, obs: &Tensor, hidden: &Hidden, train: bool) -> (Tensor, Hidden) {
+        self.network.seq(&obs.to_device(self.device), hidden, train)
+    }
+
+    pub fn zero_state(&self, batch: i64) -> Hidden {
+        self.network.zero_state(batch)
+    }
+
+    pub fn save(&mut self) {
+        let path = self.save_path.clone();
+        self.save_to(&path);
+    }
+
+    // the traced module maps (obs, hidden..) to (actions, hidden..)
+    pub fn save_to(&mut self, path: &str) {
+        self.vs.freeze();
+        let kind = self.kind;
+        let network = &self.network;
+        let mut forward_fn = |x: &[Tensor]| {
+            let hidden = Hidden::from_tensors(kind, &x[1..]);
+            let (actions, hidden) = network.step(&x[0].unsqueeze(0), &hidden, false);
+            let mut outputs = vec![actions.squeeze_dim(0)];
+            outputs.extend(hidden.tensors());
+            outputs
+        };
+        let mode = if Cuda::is_available() {
+            FLOAT_CUDA
+        } else {
+            FLOAT_CPU
+        };
+        let mut inputs = vec![Tensor::zeros([self.observation_space as i64], mode)];
+        inputs.extend(self.network.zero_state(1).tensors());
+        let cmodule =
+            CModule::create_by_tracing("RecurrentActor", "forward", &inputs, &mut forward_fn)
+                .unwrap();
+        cmodule.save(path).unwrap();
+        self.vs.unfreeze();
+    }
+
+    pub fn observation_space(&self) -> usize {
+        self.observation_space
+    }
+    pub fn action_space(&self) -> usize {
+        self.action_space
+    }
+    pub fn optimizer_mut(&mut self) -> &mut Optimizer {
+        &mut self.optimizer
+    }
+    pub fn var_store(&self) -> &VarStore {
+        &self.vs
+    }
+    pub fn var_store_mut(&mut self) -> &mut VarStore {
+        &mut self.vs
+    }
+    pub fn import(&mut self, other: &Self) {
+        self.vs.copy(&other.vs).unwrap();
+    }
+}
@@ -0,0 +1,221 @@
+use tch::Kind::{Bool, Float};
+use tch::{no_grad, Tensor};
+
+use crate::model::episodic::Episodic;
+use crate::model::learner::Learner;
+use crate::model::loss::CriticLoss;
+use crate::model::metrics::TrainMetrics;
+use crate::model::noise::Noise;
+use crate::model::recurrent_actor::RecurrentActor;
+use crate::model::recurrent_critic::RecurrentCritic;
+use crate::model::sequence_memory::SequenceMemory;
+use crate::utils::functions::{grad_norm, update_vs};
+
+/// # Recurrent agent
+///
+/// DDPG over sequences: every sampled sequence starts with `burn_in` steps
+/// that only warm up the hidden states, the losses use the following `seq_len` steps
+/// except the zero padding of sequences sampled from short episodes
+pub struct RecurrentAgent {
+    actor: RecurrentActor,
+    actor_target: RecurrentActor,
+    critic: RecurrentCritic,
+    critic_target: RecurrentCritic,
+    memory: SequenceMemory,
+    noise: Noise,
+    train: bool,
+    gamma: f64,
+    tau: f64,
+    critic_loss: CriticLoss,
+    actor_grad_clip: Option<f64>,
+    critic_grad_clip: Option<f64>,
+    burn_in: usize,
+    seq_len: usize,
+}
+
+impl RecurrentAgent {
+    pub fn new(
+        actor: RecurrentActor,
+        actor_target: RecurrentActor,
+        critic: RecurrentCritic,
+        critic_target: RecurrentCritic,
+        noise: Noise,
+        mem_dim: usize,
+        train: bool,
+        gamma: f64,
+        tau: f64,
+        critic_loss: CriticLoss,
+        actor_grad_clip: Option<f64>,
+        critic_grad_clip: Option<f64>,
+        burn_in: usize,
+        seq_len: usize,
+    ) -> Self {
+        Self {
+            actor,
+            actor_target,
+            critic,
+            critic_target,
+            memory: SequenceMemory::new(mem_dim),
+            noise,
+            train,
+            gamma,
+            tau,
+            critic_loss,
+            actor_grad_clip,
+            critic_grad_clip,
+            burn_in,
+            seq_len,
+        }
+    }
+}
+
+impl Episodic for RecurrentAgent {
+    // the episode being played is stored
+    fn reset(&mut self) {
+        self.actor.reset();
+        self.memory.end_episode();
+    }
+}
+
+impl Learner for RecurrentAgent {
+    fn actions(&mut self, obs: &Tensor) -> Tensor {
+        let mut actions = self.actor.act(obs);
+        if self.train {
+            actions += self.noise.sample();
+        }
+        actions
+    }
+
+    fn greedy_actions(&mut self, obs: &Tensor) -> Tensor {
+        self.actor.act(obs)
+    }
+
+    fn remember(&mut self, obs: &Tensor, actions: &Tensor, reward: &Tensor, next_obs: &Tensor) {
+        self.memory.push(obs, actions, reward, next_obs);
+    }
+
+    fn train(&mut self, batch_size: usize) -> Option<TrainMetrics> {
+        let (states, actions, rewards, next_states, mask) =
+            self.memory
+                .random_batch(batch_size, self.burn_in, self.seq_len)?;
+        let batch = states.size()[0];
+        let (burn_in, seq_len) = (self.burn_in as i64, self.seq_len as i64);
+        let split = |xs: &Tensor| (xs.narrow(1, 0, burn_in), xs.narrow(1, burn_in, seq_len));
+        let (burn_states, states) = split(&states);
+        let (burn_actions, actions) = split(&actions);
+        let (burn_next_states, next_states) = split(&next_states);
+        let rewards = rewards.narrow(1, burn_in, seq_len);
+        let mask = mask.narrow(1, burn_in, seq_len);
+
+        // warm up the hidden states without tracking the gradients
+        let (actor_hidden, actor_target_hidden, critic_hidden, critic_target_hidden) =
+            no_grad(|| {
+                let (_, actor_hidden) =
+                    self.actor
+                        .forward_seq(&burn_states, &self.actor.zero_state(batch), false);
+                let (burn_next_actions, actor_target_hidden) = self.actor_target.forward_seq(
+                    &burn_next_states,
+                    &self.actor_target.zero_state(batch),
+                    false,
+                );
+                let (_, critic_hidden) = self.critic.forward_seq(
+                    &burn_states,
+                    &burn_actions,
+                    &self.critic.zero_state(batch),
+                    false,
+                );
+                let (_, critic_target_hidden) = self.critic_target.forward_seq(
+                    &burn_next_states,
+                    &burn_next_actions,
+                    &self.critic_target.zero_state(batch),
+                    false,
+                );
+                (
+                    actor_hidden.detach(),
+                    actor_target_hidden.detach(),
+                    critic_hidden.detach(),
+                    critic_target_hidden.detach(),
+                )
+            });
+
+        let q_target = no_grad(|| {
+            let (next_actions, _) =
+                self.actor_target
+                    .forward_seq(&next_states, &actor_target_hidden, false);
+            let (q_next, _) = self.critic_target.forward_seq(
+                &next_states,
+                &next_actions,
+                &critic_target_hidden,
+                false,
+            );
+            &rewards + self.gamma * q_next
+        });
+
+        let (q, _) = self
+            .critic
+            .forward_seq(&states, &actions, &critic_hidden, true);
+        let td_error = (&q_target - &q)
+            .detach()
+            .abs()
+            .masked_select(&mask.to_kind(Bool));
+        let critic_loss = self.critic_loss.compute_masked(&q, &q_target, &mask);
+
+        self.critic.optimizer_mut().zero_grad();
+        critic_loss.backward();
+        let critic_grad_norm = grad_norm(self.critic.var_store());
+        if let Some(max) = self.critic_grad_clip {
+            self.critic.optimizer_mut().clip_grad_norm(max);
+        }
+        self.critic.optimizer_mut().step();
+
+        let (predicted_actions, _) = self.actor.forward_seq(&states, &actor_hidden, true);
+        let (actor_q, _) =
+            self.critic
+                .forward_seq(&states, &predicted_actions, &critic_hidden, true);
+        let actor_loss = -(actor_q * &mask).sum(Float) / mask.sum(Float);
+
+        self.actor.optimizer_mut().zero_grad();
+        actor_loss.backward();
+        let actor_grad_norm = grad_norm(self.actor.var_store());
+        if let Some(max) = self.actor_grad_clip {
+            self.actor.optimizer_mut().clip_grad_norm(max);
+        }
+        self.actor.optimizer_mut().step();
+
+        update_vs(
+            self.critic_target.var_store_mut(),
+            self.critic.var_store(),
+            self.tau,
+        );
+        update_vs(
+            self.actor_target.var_store_mut(),
+            self.actor.var_store(),
+            self.tau,
+        );
+
+        Some(TrainMetrics {
+            critic_loss: critic_loss.double_value(&[]),
+            actor_loss: actor_loss.double_value(&[]),
+            critic_grad_norm,
+            actor_grad_norm,
+            mean_q: ((&q * &mask).sum(Float) / mask.sum(Float)).double_value(&[]),
+            max_q: q.masked_select(&mask.to_kind(Bool)).max().double_value(&[]),
+            td_error_mean: td_error.mean(Float).double_value(&[]),
+            td_error_std: td_error.std(true).double_value(&[]),
+            td_error_max: td_error.max().double_value(&[]),
+        })
+    }
+
+    fn set_lr(&mut self, actor_lr: f64, critic_lr: f64) {
+        self.actor.optimizer_mut().set_lr(actor_lr);
+        self.critic.optimizer_mut().set_lr(critic_lr);
+    }
+
+    fn save(&mut self) {
+        self.actor.save();
+    }
+
+    fn save_to(&mut self, path: &str) {
+        self.actor.save_to(path);
+    }
+}
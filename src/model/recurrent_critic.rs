@@ -0,0 +1,76 @@
+use crate::model::recurrent::{Hidden, RecurrentNet, RnnKind};
+use crate::model::spec::NetworkSpec;
+use tch::nn::{Adam, Optimizer, OptimizerConfig, VarStore};
+use tch::{Device, Tensor};
+
+/// # Recurrent critic
+///
+/// Scalar Q values for sequences of concatenated actions and observations
+pub struct RecurrentCritic {
+    vs: VarStore,
+    network: RecurrentNet,
+    device: Device,
+    optimizer: Optimizer,
+}
+
+impl RecurrentCritic {
+    pub fn new(
+        observation_space: usize,
+        action_space: usize,
+        lr: f64,
+        hidden_size: i64,
+        kind: RnnKind,
+        spec: &NetworkSpec,
+    ) -> Self {
+        let device = Device::cuda_if_available();
+        let vs = VarStore::new(device);
+        let optimizer = Adam::default().build(&vs, lr).unwrap();
+        let p = &vs.root();
+        let network = RecurrentNet::new(
+            p,
+            (observation_space + action_space) as i64,
+            hidden_size,
+            1,
+            kind,
+            spec,
+        );
+        Self {
+            network,
+            device: p.device(),
+            vs,
+            optimizer,
+        }
+    }
+
+    // Q values for a batch of sequences, shape [batch, seq, 1]
+    pub fn forward_seq(
+        &self,
+        obs: &Tensor,
+        actions: &Tensor,
+        hidden: &Hidden,
+        train: bool,
+    ) -> (Tensor, Hidden) {
+        let xs = Tensor::cat(
+            &[actions.to_device(self.device), obs.to_device(self.device)],
+            2,
+        );
+        self.network.seq(&xs, hidden, train)
+    }
+
+    pub fn zero_state(&self, batch: i64) -> Hidden {
+        self.network.zero_state(batch)
+    }
+
+    pub fn optimizer_mut(&mut self) -> &mut Optimizer {
+        &mut self.optimizer
+    }
+    pub fn var_store(&self) -> &VarStore {
+        &self.vs
+    }
+    pub fn var_store_mut(&mut self) -> &mut VarStore {
+        &mut self.vs
+    }
+    pub fn import(&mut self, other: &Self) {
+        self.vs.copy(&other.vs).unwrap();
+    }
+}
@@ -0,0 +1,38 @@
+use std::f64::consts::PI;
+
+/// # Learning rate scheduler
+///
+/// - `Constant`: always the base learning rate
+/// - `Step`: multiply by `gamma` every `step_size` episodes
+/// - `Cosine`: cosine annealing from the base learning rate to `min_lr` over `t_max` episodes
+/// - `Linear`: linear decay to `end_factor` times the base learning rate over `total` episodes
+///
+/// Periods of 0 episodes count as 1
+#[derive(Clone, Copy, Debug)]
+pub enum LrScheduler {
+    Constant,
+    Step { step_size: usize, gamma: f64 },
+    Cosine { t_max: usize, min_lr: f64 },
+    Linear { total: usize, end_factor: f64 },
+}
+
+impl LrScheduler {
+    pub fn lr(&self, base_lr: f64, episode: usize) -> f64 {
+        match *self {
+            LrScheduler::Constant => base_lr,
+            LrScheduler::Step { step_size, gamma } => {
+                base_lr * gamma.powi((episode / step_size.max(1)) as i32)
+            }
+            LrScheduler::Cosine { t_max, min_lr } => {
+                let t_max = t_max.max(1);
+                let t = episode.min(t_max) as f64 / t_max as f64;
+                min_lr + (base_lr - min_lr) * (1. + (PI * t).cos()) / 2.
+            }
+            LrScheduler::Linear { total, end_factor } => {
+                let total = total.max(1);
+                let t = episode.min(total) as f64 / total as f64;
+                base_lr * (1. + (end_factor - 1.) * t)
+            }
+        }
+    }
+}
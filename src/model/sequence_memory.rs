@@ -0,0 +1,175 @@
+use rand::seq::IteratorRandom;
+use rand::{thread_rng, Rng};
+use std::collections::VecDeque;
+use std::mem::take;
+use tch::Kind::Float;
+use tch::Tensor;
+
+type Transition = (Tensor, Tensor, Tensor, Tensor);
+
+/// # Sequence replay memory
+///
+/// Stores whole episodes so that sequences of consecutive transitions can be sampled
+///
+/// - `episodes`: finished episodes, the oldest are dropped first
+/// - `current`: transitions of the episode being played
+/// - `capacity`: maximum number of stored transitions
+/// - `len`: number of stored transitions
+pub struct SequenceMemory {
+    episodes: VecDeque<Vec<Transition>>,
+    current: Vec<Transition>,
+    capacity: usize,
+    len: usize,
+}
+
+impl SequenceMemory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            episodes: VecDeque::new(),
+            current: vec![],
+            capacity,
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, obs: &Tensor, actions: &Tensor, reward: &Tensor, next_obs: &Tensor) {
+        self.current.push((
+            obs.copy(),
+            actions.copy(),
+            reward.to_kind(Float).to_device(obs.device()).reshape([1]),
+            next_obs.copy(),
+        ));
+    }
+
+    // store the episode being played
+    pub fn end_episode(&mut self) {
+        if self.current.is_empty() {
+            return;
+        }
+        self.len += self.current.len();
+        self.episodes.push_back(take(&mut self.current));
+        while self.len > self.capacity {
+            self.len -= self.episodes.pop_front().unwrap().len();
+        }
+    }
+
+    // sequences of `burn_in + seq_len` transitions, the ones sampled from shorter episodes
+    // are padded with zeros at the start, every tensor has shape [batch, burn_in + seq_len, ..]
+    // and the mask is 1 for the real transitions and 0 for the padding
+    pub fn random_batch(
+        &self,
+        batch_size: usize,
+        burn_in: usize,
+        seq_len: usize,
+    ) -> Option<(Tensor, Tensor, Tensor, Tensor, Tensor)> {
+        if self.episodes.is_empty() {
+            return None;
+        }
+
+        let total = burn_in + seq_len;
+        let mut rng = thread_rng();
+        let (mut obs, mut actions, mut rewards, mut next_obs, mut masks) =
+            (vec![], vec![], vec![], vec![], vec![]);
+        for _ in 0..batch_size {
+            let episode = self.episodes.iter().choose(&mut rng).unwrap();
+            let start = rng.gen_range(0..=episode.len().saturating_sub(total));
+            let sequence = &episode[start..episode.len().min(start + total)];
+            let padding = total - sequence.len();
+            obs.push(padded(sequence.iter().map(|t| &t.0), padding));
+            actions.push(padded(sequence.iter().map(|t| &t.1), padding));
+            rewards.push(padded(sequence.iter().map(|t| &t.2), padding));
+            next_obs.push(padded(sequence.iter().map(|t| &t.3), padding));
+            masks.push(Tensor::cat(
+                &[
+                    Tensor::zeros([padding as i64, 1], (Float, sequence[0].2.device())),
+                    Tensor::ones([sequence.len() as i64, 1], (Float, sequence[0].2.device())),
+                ],
+                0,
+            ));
+        }
+        Some((
+            Tensor::stack(&obs, 0),
+            Tensor::stack(&actions, 0),
+            Tensor::stack(&rewards, 0),
+            Tensor::stack(&next_obs, 0),
+            Tensor::stack(&masks, 0),
+        ))
+    }
+}
+
+// stack the tensors of a sequence after `padding` zero tensors of the same shape
+fn padded<'a>(tensors: impl Iterator<Item = &'a Tensor>, padding: usize) -> Tensor {
+    let tensors = tensors.collect::<Vec<_>>();
+    let zeros = tensors[0].zeros_like();
+    let mut sequence = vec![&zeros; padding];
+    sequence.extend(tensors);
+    Tensor::stack(&sequence, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // episode of `len` transitions whose observations count the steps from 1
+    fn episode(memory: &mut SequenceMemory, len: usize) {
+        for step in 1..=len {
+            let obs = Tensor::from_slice(&[step as f32, step as f32]);
+            let actions = Tensor::from_slice(&[0f32, 1.]);
+            memory.push(&obs, &actions, &Tensor::from(1f32), &(&obs + 1));
+        }
+        memory.end_episode();
+    }
+
+    fn values(tensor: &Tensor) -> Vec<f32> {
+        Vec::<f32>::try_from(&tensor.view([-1])).unwrap()
+    }
+
+    #[test]
+    fn empty_memory() {
+        let mut memory = SequenceMemory::new(10);
+        assert!(memory.random_batch(4, 1, 2).is_none());
+        // an episode without transitions isn't stored
+        memory.end_episode();
+        assert!(memory.random_batch(4, 1, 2).is_none());
+    }
+
+    #[test]
+    fn short_episodes_are_padded_at_the_start() {
+        let mut memory = SequenceMemory::new(10);
+        episode(&mut memory, 2);
+        let (obs, actions, rewards, next_obs, mask) = memory.random_batch(3, 1, 3).unwrap();
+        assert_eq!(obs.size(), [3, 4, 2]);
+        assert_eq!(actions.size(), [3, 4, 2]);
+        assert_eq!(rewards.size(), [3, 4, 1]);
+        assert_eq!(next_obs.size(), [3, 4, 2]);
+        assert_eq!(mask.size(), [3, 4, 1]);
+        for i in 0..3 {
+            assert_eq!(values(&mask.get(i)), [0., 0., 1., 1.]);
+            assert_eq!(values(&rewards.get(i)), [0., 0., 1., 1.]);
+            assert_eq!(values(&obs.get(i)), [0., 0., 0., 0., 1., 1., 2., 2.]);
+            assert_eq!(values(&next_obs.get(i)), [0., 0., 0., 0., 2., 2., 3., 3.]);
+        }
+    }
+
+    #[test]
+    fn long_episodes_are_not_padded() {
+        let mut memory = SequenceMemory::new(10);
+        episode(&mut memory, 6);
+        let (obs, _, _, _, mask) = memory.random_batch(5, 2, 2).unwrap();
+        assert_eq!(values(&mask), [1.; 20]);
+        for i in 0..5 {
+            // consecutive steps of the episode
+            let steps = values(&obs.get(i).select(1, 0));
+            assert!(steps.windows(2).all(|pair| pair[1] == pair[0] + 1.));
+        }
+    }
+
+    #[test]
+    fn oldest_episodes_are_dropped() {
+        let mut memory = SequenceMemory::new(5);
+        episode(&mut memory, 3);
+        episode(&mut memory, 4);
+        assert_eq!(memory.episodes.len(), 1);
+        assert_eq!(memory.len, 4);
+    }
+}
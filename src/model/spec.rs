@@ -0,0 +1,61 @@
+pub use crate::export::activation::Activation;
+use crate::model::encoder::EncoderSpec;
+use tch::Tensor;
+
+impl Activation {
+    pub fn apply(&self, xs: &Tensor) -> Tensor {
+        match self {
+            Activation::Relu => xs.relu(),
+            Activation::LeakyRelu => xs.leaky_relu(),
+            Activation::Tanh => xs.tanh(),
+            Activation::Elu => xs.elu(),
+            Activation::Gelu => xs.gelu("none"),
+        }
+    }
+}
+
+/// # Normalization applied after every hidden linear layer
+///
+/// `Batch` normalizes over the batch, single observations are treated as a batch of one
+#[derive(Clone, Copy, Debug)]
+pub enum Norm {
+    None,
+    Layer,
+    Batch,
+}
+
+/// # Weight initialization of the hidden layers
+///
+/// - `Default`: the tch default (kaiming uniform)
+/// - `Orthogonal`: orthogonal weights scaled by `gain` and zero biases
+/// - `FanInUniform`: uniform in ±1/sqrt(fan in) as in the DDPG paper
+#[derive(Clone, Copy, Debug)]
+pub enum InitScheme {
+    Default,
+    Orthogonal { gain: f64 },
+    FanInUniform,
+}
+
+/// # Network specification
+///
+/// - `layers`: width of every hidden layer
+/// - `activation`: activation after every hidden layer
+/// - `norm`: normalization before every activation
+/// - `dropout`: dropout probability after every activation, 0 disables it
+/// - `init`: initialization of the hidden layers
+/// - `final_init`: if set the output layer is initialized uniformly in ±`final_init`
+/// - `action_layer`: critic only, number of hidden layers processing the observation
+///   before the actions get concatenated (0 = at the input)
+/// - `encoder`: convolutional encoder of the grid observation, requires `GRID_OBSERVATION`
+///   and is ignored by the recurrent networks
+#[derive(Clone, Copy, Debug)]
+pub struct NetworkSpec {
+    pub layers: &'static [i64],
+    pub activation: Activation,
+    pub norm: Norm,
+    pub dropout: f64,
+    pub init: InitScheme,
+    pub final_init: Option<f64>,
+    pub action_layer: usize,
+    pub encoder: Option<EncoderSpec>,
+}
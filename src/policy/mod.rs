@@ -0,0 +1,41 @@
+mod robot;
+pub use robot::PolicyRobot;
+
+use crate::export::mlp::MlpPolicy;
+use crate::model::{load_model, LoadedActor};
+use crate::utils::functions::greedy_action;
+use tch::Tensor;
+
+/// # Policy
+///
+/// Maps the observation built by `State::features` to the index of the action to perform
+pub trait Policy {
+    fn act(&mut self, obs: &[f32]) -> usize;
+}
+
+impl Policy for MlpPolicy {
+    fn act(&mut self, obs: &[f32]) -> usize {
+        MlpPolicy::act(self, obs)
+    }
+}
+
+/// # TorchScript policy
+///
+/// Actor saved by `Actor::save` or `RecurrentActor::save`, it needs libtorch
+pub struct TorchPolicy {
+    model: LoadedActor,
+}
+
+impl TorchPolicy {
+    pub fn load(path: &str) -> Self {
+        Self {
+            model: load_model(path),
+        }
+    }
+}
+
+impl Policy for TorchPolicy {
+    fn act(&mut self, obs: &[f32]) -> usize {
+        greedy_action(&self.model.forward(&Tensor::from_slice(obs))) as usize
+    }
+}
@@ -0,0 +1,94 @@
+use crate::gym::robot::GymRobot;
+use crate::gym::state::State;
+use crate::policy::Policy;
+use robotics_lib::energy::Energy;
+use robotics_lib::event::events::Event;
+use robotics_lib::runner::backpack::BackPack;
+use robotics_lib::runner::Runnable;
+use robotics_lib::world::coordinates::Coordinate;
+use robotics_lib::world::World;
+use std::cell::{Ref, RefCell};
+use std::rc::Rc;
+
+/// # Policy robot
+///
+/// Robot driven by a trained policy that can run in any robotics_lib world,
+/// the observations and actions are the ones of the `GymRobot` it wraps.
+/// Every tick performs one action, once the goals are reached it stays idle
+///
+/// - `robot`: robot keeping the observation up to date and executing the actions
+/// - `policy`: picks the action from the observation
+/// - `setup`: whether the observation still has to be initialized
+pub struct PolicyRobot<P: Policy> {
+    robot: GymRobot,
+    policy: P,
+    setup: bool,
+}
+
+impl<P: Policy> PolicyRobot<P> {
+    pub fn new(policy: P, coins_destroyed_goal: usize, coins_stored_goal: usize) -> Self {
+        Self {
+            robot: GymRobot::new(
+                Rc::new(RefCell::new(State::default())),
+                coins_destroyed_goal,
+                coins_stored_goal,
+                None,
+            ),
+            policy,
+            setup: true,
+        }
+    }
+
+    pub fn state(&self) -> Ref<State> {
+        self.robot.state.borrow()
+    }
+
+    pub fn done(&self) -> bool {
+        self.robot.state.borrow().done
+    }
+}
+
+impl<P: Policy> Runnable for PolicyRobot<P> {
+    fn process_tick(&mut self, world: &mut World) {
+        // the first tick of the gym robot only builds the observation
+        if self.setup {
+            self.robot.process_tick(world);
+            self.setup = false;
+        }
+        if self.done() {
+            return;
+        }
+        let obs = self.robot.state.borrow().features();
+        let action = self.policy.act(&obs);
+        self.robot.state.borrow_mut().action = action as i64;
+        self.robot.process_tick(world);
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        self.robot.handle_event(event)
+    }
+
+    fn get_energy(&self) -> &Energy {
+        self.robot.get_energy()
+    }
+
+    fn get_energy_mut(&mut self) -> &mut Energy {
+        self.robot.get_energy_mut()
+    }
+
+    fn get_coordinate(&self) -> &Coordinate {
+        self.robot.get_coordinate()
+    }
+
+    fn get_coordinate_mut(&mut self) -> &mut Coordinate {
+        self.robot.get_coordinate_mut()
+    }
+
+    fn get_backpack(&self) -> &BackPack {
+        self.robot.get_backpack()
+    }
+
+    fn get_backpack_mut(&mut self) -> &mut BackPack {
+        self.robot.get_backpack_mut()
+    }
+}
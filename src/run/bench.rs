@@ -0,0 +1,38 @@
+use crate::gym::GymEnv;
+use crate::utils::consts::{MAP_BASE, TASK};
+use crate::world::WorldSource;
+use std::path::PathBuf;
+use std::time::Instant;
+
+// resets per second of a map read at every reset and of the same map kept in memory
+pub fn bench(map: PathBuf, resets: usize) {
+    let path = if map.exists() {
+        map
+    } else {
+        PathBuf::from(MAP_BASE).join(map)
+    };
+    println!("Resets of {}", path.display());
+    println!(
+        "|{:_^10}|{:_^8}|{:_^12}|{:_^12}|",
+        "World", "Resets", "Seconds", "Resets/s"
+    );
+    let mut rates = vec![];
+    for (name, source) in [
+        ("uncached", WorldSource::open(&path)),
+        ("cached", WorldSource::open(&path).cached()),
+    ] {
+        let mut env = GymEnv::new(source, TASK.collect_goal, TASK.deposit_goal);
+        let start = Instant::now();
+        for _ in 0..resets {
+            env.reset();
+        }
+        let seconds = start.elapsed().as_secs_f64();
+        let rate = resets as f64 / seconds;
+        println!(
+            "|{:^10}|{:^8}|{:^12.3}|{:^12.1}|",
+            name, resets, seconds, rate
+        );
+        rates.push(rate);
+    }
+    println!("The cache is {:.1}x faster", rates[1] / rates[0]);
+}
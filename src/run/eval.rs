@@ -1,109 +1,160 @@
+use crate::gym::options::ResetOptions;
+use crate::gym::recorder::{Frame, Recording};
 use crate::gym::GymEnv;
+use crate::model::{load_model, LoadedActor};
 use crate::utils::consts::{
-    COINS_DESTROYED_TARGET, COINS_STORED_TARGET, EVAL_LOG, EVAL_PLOT, EVAL_STATE, MAPS, MAP_BASE,
-    MAX_EP, MODEL_BASE, N_WORKERS,
+    EVAL_DIR, EVAL_LOG, EVAL_PLOT, EVAL_REPLAY, EVAL_RESET_OPTIONS, EVAL_STATE, EVAL_SUMMARY,
+    EVAL_WEATHER, MAPS, MAX_EP, MODEL_BASE, N_WORKERS, TASK,
 };
-use crate::utils::functions::plot;
-use std::fs::File;
+use crate::utils::functions::{greedy_action, new_episode, plot, run_episode};
+use crate::utils::runs::RunDir;
+use crate::utils::stats::{print_summary_table, weather_summaries, EpisodeStats, EvalSummary};
+use crate::world::WorldSource;
+use std::fs::{create_dir_all, File};
 use std::io::Write;
 use std::thread::spawn;
-use tch::{CModule, Device};
-use worldgen_unwrap::public::WorldgeneratorUnwrap;
 
-pub fn eval() {
+pub fn eval(episodes: usize, seeds: Vec<u64>, random_weather: bool, run: Option<String>) {
+    let run = RunDir::resolve(run);
+    create_dir_all(run.file(EVAL_DIR)).unwrap();
     let mut handles = vec![];
     (0..N_WORKERS).for_each(|worker| {
+        let seeds = seeds.clone();
+        let run = run.clone();
         handles.push(spawn(move || {
-            let generator = WorldgeneratorUnwrap::init(
-                false,
-                Some(format!("{}/{}", MAP_BASE, MAPS[worker]).into()),
+            let mut env = GymEnv::new(
+                WorldSource::map(MAPS[worker]),
+                TASK.collect_goal,
+                TASK.deposit_goal,
             );
-            let mut env = GymEnv::new(generator, COINS_DESTROYED_TARGET, COINS_STORED_TARGET);
-            let mut model = CModule::load_on_device(
-                format!("{}_{}.pt", MODEL_BASE, worker),
-                Device::cuda_if_available(),
-            )
-            .unwrap();
-            model.set_eval();
-            let mut log_file = File::create(format!("{}_{}.log", EVAL_LOG, worker)).unwrap();
-            let mut state_log_file =
-                File::create(format!("{}_{}.log", EVAL_STATE, worker)).unwrap();
-            log_file
-                .write_all(
-                    format!(
-                        "|{:_^11}|{:_^8}|{:_^12}|{:_^7}|{:_^12}|\n",
-                        "Iteration", "Action", "Reward", "Done", "Acc. Reward"
-                    )
-                    .as_bytes(),
-                )
-                .unwrap();
-            state_log_file
-                .write_all(
-                    format!(
-                        "|{:_^22}|{:_^22}|{:_^22}|{:_^22}|{:_^22}|\n",
-                        "Danger",
-                        "Coin Direction",
-                        "Coin Adjacency",
-                        "Bank Direction",
-                        "Bank Adjacency"
-                    )
-                    .as_bytes(),
-                )
-                .unwrap();
-
-            let mut memory = vec![];
-            let mut min_rw = f64::MAX;
-            let mut max_rw = f64::MIN;
-            let mut acc_rw = 0.;
-            let mut i = 0;
-
-            let mut obs = env.reset();
-            loop {
-                let actions = obs.apply(&model);
-                let action = actions
-                    .softmax(-1, tch::Kind::Float)
-                    .argmax(-1, true)
-                    .int64_value(&[]);
-                let step = env.step(action);
-
-                acc_rw += step.reward;
-                memory.push(acc_rw);
-                if acc_rw < min_rw {
-                    min_rw = acc_rw;
-                }
-                if acc_rw > max_rw {
-                    max_rw = acc_rw;
-                }
-
-                // log to file
-                let log = format!(
-                    "|{:^11}|{:^8}|{:^12.3}|{:^7}|{:^12.3}|\n",
-                    i, action, step.reward, step.done, acc_rw
-                );
-                log_file.write_all(log.as_bytes()).unwrap();
-                let state = env.state();
-                let state_log = format!(
-                    "| {:?} | {:?} | {:?} | {:?} | {:?} |\n",
-                    state.danger, state.coin_dir, state.coin_adj, state.bank_dir, state.bank_adj,
-                );
-                state_log_file.write_all(state_log.as_bytes()).unwrap();
+            let mut model = load_model(&run.file(&format!("{}_{}.pt", MODEL_BASE, worker)));
 
-                if i >= MAX_EP || step.done {
-                    break;
+            let mut stats = vec![];
+            for (s, &seed) in seeds.iter().enumerate() {
+                // the seed draws the start of the episodes, a greedy policy is deterministic
+                let options = ResetOptions {
+                    random_weather,
+                    ..EVAL_RESET_OPTIONS
+                };
+                env.set_options(options, seed);
+                for episode in 0..episodes {
+                    // only the first episode gets logged, plotted and recorded
+                    let ep_stats = if s == 0 && episode == 0 {
+                        detailed_episode(&mut env, &mut model, worker, &run)
+                    } else {
+                        let obs = new_episode(&mut env, &mut model);
+                        run_episode(
+                            &mut env,
+                            obs,
+                            MAX_EP,
+                            |obs| greedy_action(&model.forward(obs)),
+                            |_, _, _| {},
+                        )
+                    };
+                    stats.push(ep_stats);
                 }
-                obs = step.obs;
-                i += 1;
             }
-            println!("T: {worker}, evaluation: {acc_rw:.4}");
-            plot(
-                format!("{}_{}.png", EVAL_PLOT, worker),
-                memory,
-                min_rw,
-                max_rw,
+            let summary = EvalSummary::new(&stats);
+            println!(
+                "T: {worker}, evaluation: {:.4} ± {:.4}",
+                summary.mean_return, summary.std_return
             );
+            let weathers = weather_summaries(&stats)
+                .into_iter()
+                .map(|(weather, summary)| (format!("{} {}", MAPS[worker], weather), summary))
+                .collect::<Vec<_>>();
+            ((MAPS[worker].to_string(), summary), weathers)
         }));
     });
-    for handle in handles {
-        handle.join().unwrap();
+    let (summaries, weathers): (Vec<_>, Vec<_>) = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .unzip();
+    let weathers = weathers.concat();
+    print_summary_table(&summaries);
+    let file = File::create(run.file(EVAL_SUMMARY)).unwrap();
+    serde_json::to_writer_pretty(file, &summaries).unwrap();
+    // with the conditions of the maps every map has a single weather row
+    if random_weather {
+        println!("By weather");
+        print_summary_table(&weathers);
+        let file = File::create(run.file(EVAL_WEATHER)).unwrap();
+        serde_json::to_writer_pretty(file, &weathers).unwrap();
     }
 }
+
+fn detailed_episode(
+    env: &mut GymEnv,
+    model: &mut LoadedActor,
+    worker: usize,
+    run: &RunDir,
+) -> EpisodeStats {
+    let mut log_file = File::create(run.file(&format!("{}_{}.log", EVAL_LOG, worker))).unwrap();
+    let mut state_log_file =
+        File::create(run.file(&format!("{}_{}.log", EVAL_STATE, worker))).unwrap();
+    log_file
+        .write_all(
+            format!(
+                "|{:_^11}|{:_^8}|{:_^12}|{:_^7}|{:_^12}|{:_^8}|\n",
+                "Iteration", "Action", "Reward", "Done", "Acc. Reward", "Energy"
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+    state_log_file
+        .write_all(
+            format!(
+                "|{:_^22}|{:_^22}|{:_^22}|{:_^22}|{:_^22}|\n",
+                "Danger", "Coin Direction", "Coin Adjacency", "Bank Direction", "Bank Adjacency"
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+
+    let mut memory = vec![];
+    let mut min_rw = f64::MAX;
+    let mut max_rw = f64::MIN;
+    let mut acc_rw = 0.;
+
+    let obs = new_episode(env, model);
+    let mut recording = Recording::new(MAPS[worker], worker, env.world_snapshot());
+    recording.push(Frame::capture(0, acc_rw, &env.state()));
+
+    let stats = run_episode(
+        env,
+        obs,
+        MAX_EP,
+        |obs| greedy_action(&model.forward(obs)),
+        |i, step, state| {
+            acc_rw += step.reward;
+            memory.push(acc_rw);
+            if acc_rw < min_rw {
+                min_rw = acc_rw;
+            }
+            if acc_rw > max_rw {
+                max_rw = acc_rw;
+            }
+
+            // log to file
+            let log = format!(
+                "|{:^11}|{:^8}|{:^12.3}|{:^7}|{:^12.3}|{:^8}|\n",
+                i, step.action, step.reward, step.done, acc_rw, state.energy
+            );
+            log_file.write_all(log.as_bytes()).unwrap();
+            let state_log = format!(
+                "| {:?} | {:?} | {:?} | {:?} | {:?} |\n",
+                state.danger, state.coin_dir, state.coin_adj, state.bank_dir, state.bank_adj,
+            );
+            state_log_file.write_all(state_log.as_bytes()).unwrap();
+            recording.push(Frame::capture(i + 1, acc_rw, state));
+        },
+    );
+    recording.save(&run.file(&format!("{}_{}.json", EVAL_REPLAY, worker)));
+    plot(
+        run.file(&format!("{}_{}.png", EVAL_PLOT, worker)),
+        memory,
+        min_rw,
+        max_rw,
+    );
+    stats
+}
@@ -0,0 +1,84 @@
+use crate::export::onnx::save_onnx;
+use crate::export::portable::{ModelMetadata, PortableModel};
+use crate::model::{check_model, Actor, Manifest};
+use crate::utils::consts::{
+    ACTOR_SPEC, EXPORT_BASE, EXPORT_DIR, LR_A, MODEL_BASE, N_ACTIONS, N_WORKERS, RECURRENT,
+};
+use crate::utils::functions::{observation_size, weights_path};
+use crate::utils::runs::RunDir;
+use std::fs::create_dir_all;
+use std::path::Path;
+use std::process::exit;
+
+pub fn export(workers: Vec<usize>, run: Option<String>) {
+    let run = RunDir::resolve(run);
+    create_dir_all(run.file(EXPORT_DIR)).unwrap();
+    let workers = if workers.is_empty() {
+        (0..N_WORKERS).collect()
+    } else {
+        workers
+    };
+    let observation_space = observation_size() as usize;
+    let action_space = N_ACTIONS as usize;
+    for worker in workers {
+        let model_path = run.file(&format!("{}_{}.pt", MODEL_BASE, worker));
+        if !Path::new(&model_path).exists() {
+            eprintln!("{model_path} doesn't exist");
+            exit(1);
+        }
+        let problems = export_problems(check_model(&model_path).as_ref());
+        if !problems.is_empty() {
+            eprintln!("{model_path} can't be exported: {}", problems.join(", "));
+            exit(1);
+        }
+        let weights = weights_path(&model_path);
+        if !Path::new(&weights).exists() {
+            eprintln!(
+                "{weights} not found, only the actors saved with their weights can be exported"
+            );
+            exit(1);
+        }
+        let mut actor = Actor::new(
+            observation_space,
+            action_space,
+            LR_A,
+            &ACTOR_SPEC,
+            model_path.clone(),
+        );
+        actor.var_store_mut().load(&weights).unwrap();
+        let metadata = ModelMetadata::new(observation_space, action_space, ACTOR_SPEC.activation);
+        let model = PortableModel::from_var_store(actor.var_store(), &ACTOR_SPEC, metadata);
+        model.save(&run.file(&format!("{}_{}.json", EXPORT_BASE, worker)));
+        save_onnx(
+            &model,
+            &run.file(&format!("{}_{}.onnx", EXPORT_BASE, worker)),
+        );
+        println!("T: {worker}, exported {model_path}");
+    }
+}
+
+// reasons the saved actor can't be rebuilt with the dense layers of ACTOR_SPEC,
+// actors without a manifest are assumed to follow the current configuration
+fn export_problems(manifest: Option<&Manifest>) -> Vec<String> {
+    let (recurrent, spec) = match manifest {
+        Some(manifest) => (
+            manifest.recurrent().is_some(),
+            manifest.hyperparameters.get("actor_spec").cloned(),
+        ),
+        None => (RECURRENT.is_some(), None),
+    };
+    let mut problems = vec![];
+    if recurrent {
+        problems.push("recurrent actor".to_string());
+    }
+    match spec {
+        Some(spec) if spec != format!("{:?}", ACTOR_SPEC) => {
+            problems.push(format!("trained with a different ACTOR_SPEC ({})", spec));
+        }
+        _ if ACTOR_SPEC.encoder.is_some() => {
+            problems.push("convolutional encoder".to_string());
+        }
+        _ => {}
+    }
+    problems
+}
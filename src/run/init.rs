@@ -1,6 +1,101 @@
 use crate::gym::GymEnv;
+use crate::utils::consts::MAP_BASE;
+use crate::utils::functions::{file_name, list_files};
+use crate::world::generator::MapParams;
+use crate::world::map::{MapFile, MapStats};
+use crate::world::WorldSource;
+use robotics_lib::world::world_generator::Generator;
+use std::path::{Path, PathBuf};
+use std::process::exit;
 use worldgen_unwrap::public::WorldgeneratorUnwrap;
 
+// open the interactive world generator
 pub fn init() {
-    GymEnv::new(WorldgeneratorUnwrap::init(true, None), 0, 0);
+    GymEnv::new(
+        WorldSource::Unwrap(WorldgeneratorUnwrap::init(true, None)),
+        0,
+        0,
+    );
+}
+
+// generate a map and save it as `<name>.json` in the maps folder
+pub fn generate_map(name: String, params: MapParams) {
+    let path = PathBuf::from(MAP_BASE).join(format!("{}.json", name));
+    if path.exists() {
+        eprintln!("{} already exists", path.display());
+        exit(1);
+    }
+    let map = MapFile::generate(params);
+    map.save(&path);
+    println!("Saved {}", path.display());
+    print_stats(&[(name, MapStats::new(&map.world, map.spawn))]);
+}
+
+// statistics of every map of the maps folder
+pub fn list_maps() {
+    let mut paths = list_files(MAP_BASE, "bin");
+    paths.extend(list_files(MAP_BASE, "json"));
+    let rows = paths
+        .iter()
+        .map(|path| (file_name(path), stats(path)))
+        .collect::<Vec<_>>();
+    print_stats(&rows);
+}
+
+// check that a map has a coin and a bank the robot can reach
+pub fn validate_map(map: PathBuf) {
+    let path = if map.exists() {
+        map
+    } else {
+        PathBuf::from(MAP_BASE).join(map)
+    };
+    let stats = stats(&path);
+    print_stats(&[(file_name(&path), stats.clone())]);
+    if stats.is_valid() {
+        println!("{} is valid", path.display());
+    } else {
+        eprintln!(
+            "{} is not valid: {} reachable coins and {} reachable banks",
+            path.display(),
+            stats.reachable_coins,
+            stats.reachable_banks
+        );
+        exit(1);
+    }
+}
+
+fn stats(path: &Path) -> MapStats {
+    let (world, spawn, ..) = WorldSource::open(path).gen();
+    MapStats::new(&world, spawn)
+}
+
+fn print_stats(rows: &[(String, MapStats)]) {
+    println!(
+        "|{:_^30}|{:_^9}|{:_^10}|{:_^7}|{:_^8}|{:_^7}|{:_^8}|{:_^10}|{:_^10}|{:_^7}|",
+        "Map",
+        "Size",
+        "Walkable",
+        "Coins",
+        "Amount",
+        "Banks",
+        "Danger",
+        "R. Coins",
+        "R. Banks",
+        "Valid"
+    );
+    for (name, stats) in rows {
+        println!(
+            "|{:^30}|{:^9}|{:^10.2}|{:^7}|{:^8}|{:^7}|{:^8}|{:^10}|{:^10}|{:^7}|",
+            name,
+            format!("{}x{}", stats.size.0, stats.size.1),
+            stats.walkable,
+            stats.coins,
+            stats.coin_amount,
+            stats.banks,
+            stats.danger,
+            stats.reachable_coins,
+            stats.reachable_banks,
+            stats.is_valid()
+        );
+    }
 }
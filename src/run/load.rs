@@ -1,8 +1,8 @@
 use crate::gym::GymEnv;
 use crate::model::Noise;
 use crate::utils::consts::{
-    BATCH, COINS_DESTROYED_TARGET, COINS_STORED_TARGET, EP, MAPS, MAP_BASE, MAX_EP, MU, N_WORKERS,
-    SIGMA, THETA, TRAIN_ITERATIONS, TRAIN_LOG, TRAIN_PLOT, TRAIN_STATE,
+    BATCH, EP, MAPS, MAP_BASE, MAX_EP, MU, N_WORKERS, SIGMA, TASK, THETA, TRAIN_ITERATIONS,
+    TRAIN_LOG, TRAIN_PLOT, TRAIN_STATE,
 };
 use crate::utils::functions::plot;
 use std::fs::File;
@@ -19,7 +19,7 @@ pub fn load() {
     //             false,
     //             Some(format!("{}/{}", MAP_BASE, MAPS[worker]).into()),
     //         );
-    //         let mut env = GymEnv::new(generator, COINS_DESTROYED_TARGET, COINS_STORED_TARGET);
+    //         let mut env = GymEnv::new(generator, TASK.collect_goal, TASK.deposit_goal);
     //         let observation_space = env.observation_space().iter().product::<i64>() as usize;
     //         let action_space = env.action_space() as usize;
     //         // let actor = Actor::load();
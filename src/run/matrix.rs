@@ -0,0 +1,86 @@
+use crate::gym::GymEnv;
+use crate::model::load_model;
+use crate::utils::consts::{
+    EVAL_DIR, EVAL_MATRIX, EVAL_RESET_OPTIONS, MAP_BASE, MAX_EP, MODEL_DIR, TASK,
+};
+use crate::utils::functions::{file_name, greedy_action, list_files, new_episode, run_episode};
+use crate::utils::runs::RunDir;
+use crate::utils::stats::{print_matrix, EvalSummary, MatrixEntry};
+use crate::world::WorldSource;
+use std::collections::HashSet;
+use std::fs::{create_dir_all, File};
+use std::path::PathBuf;
+use std::process::exit;
+use std::thread::spawn;
+
+// evaluate every saved model on every map, one thread per model
+pub fn matrix(episodes: usize, seed: u64, extra_maps: Vec<PathBuf>, run: Option<String>) {
+    let run = RunDir::resolve(run);
+    create_dir_all(run.file(EVAL_DIR)).unwrap();
+    let models = list_files(&run.file(MODEL_DIR), "pt");
+    let mut maps = list_files(MAP_BASE, "bin");
+    maps.extend(list_files(MAP_BASE, "json"));
+    maps.extend(extra_maps);
+    // the entries are keyed by file name
+    let mut names = HashSet::new();
+    if let Some(map) = maps.iter().find(|map| !names.insert(file_name(map))) {
+        eprintln!(
+            "{} has the same name as another map, rename it to evaluate on both",
+            map.display()
+        );
+        exit(1);
+    }
+
+    let mut handles = vec![];
+    for model_path in models.iter().cloned() {
+        let maps = maps.clone();
+        handles.push(spawn(move || {
+            let mut model = load_model(model_path.to_str().unwrap());
+            let mut entries = vec![];
+            for map_path in maps {
+                let mut env = GymEnv::new(
+                    WorldSource::load(&map_path),
+                    TASK.collect_goal,
+                    TASK.deposit_goal,
+                );
+                // every map gets the same sequence of spawns
+                env.set_options(EVAL_RESET_OPTIONS, seed);
+                let stats = (0..episodes)
+                    .map(|_| {
+                        let obs = new_episode(&mut env, &mut model);
+                        run_episode(
+                            &mut env,
+                            obs,
+                            MAX_EP,
+                            |obs| greedy_action(&model.forward(obs)),
+                            |_, _, _| {},
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let entry = MatrixEntry {
+                    model: file_name(&model_path),
+                    map: file_name(&map_path),
+                    summary: EvalSummary::new(&stats),
+                };
+                println!(
+                    "{} on {}: {:.4}",
+                    entry.model, entry.map, entry.summary.mean_return
+                );
+                entries.push(entry);
+            }
+            entries
+        }));
+    }
+    let entries = handles
+        .into_iter()
+        .flat_map(|handle| handle.join().unwrap())
+        .collect::<Vec<MatrixEntry>>();
+
+    print_matrix(
+        &entries,
+        &models.iter().map(|p| file_name(p)).collect::<Vec<_>>(),
+        &maps.iter().map(|p| file_name(p)).collect::<Vec<_>>(),
+    );
+    let file = File::create(run.file(EVAL_MATRIX)).unwrap();
+    serde_json::to_writer_pretty(file, &entries).unwrap();
+}
@@ -1,8 +1,20 @@
+mod bench;
 mod eval;
+mod export;
 mod init;
 mod load;
+mod matrix;
+mod replay;
+mod runs;
 mod train;
+mod verify;
+pub use bench::bench;
 pub use eval::eval;
-pub use init::init;
+pub use export::export;
+pub use init::{generate_map, init, list_maps, validate_map};
 pub use load::load;
+pub use matrix::matrix;
+pub use replay::replay;
+pub use runs::{compare_runs, list_runs, show_run};
 pub use train::train;
+pub use verify::verify;
@@ -0,0 +1,103 @@
+use crate::gym::recorder::{Frame, Recording};
+use crate::utils::consts::{EVAL_REPLAY, REPLAY_CLEAR};
+use crate::utils::functions::{action_name, tile_symbol};
+use crate::utils::runs::RunDir;
+use std::io::stdin;
+
+pub fn replay(worker: usize, run: Option<String>) {
+    let run = RunDir::resolve(run);
+    let recording = Recording::load(&run.file(&format!("{}_{}.json", EVAL_REPLAY, worker)));
+    if recording.frames.is_empty() {
+        println!("Empty recording");
+        return;
+    }
+    let mut i = 0;
+    loop {
+        render(&recording, i);
+        println!("[enter] next | [b] back | [q] quit");
+        let mut input = String::new();
+        stdin().read_line(&mut input).unwrap();
+        match input.trim() {
+            "q" => break,
+            "b" => i = i.saturating_sub(1),
+            _ if i + 1 < recording.frames.len() => i += 1,
+            _ => break,
+        }
+    }
+}
+
+fn render(recording: &Recording, i: usize) {
+    let frame = &recording.frames[i];
+    print!("{}", REPLAY_CLEAR);
+    println!(
+        "Map: {} | Worker: {} | Frame: {}/{}",
+        recording.map,
+        recording.worker,
+        i,
+        recording.frames.len() - 1
+    );
+    println!(
+        "Iteration: {} | Action: {} | Reward: {:.3} | Acc. Reward: {:.3} | Done: {}",
+        frame.iteration,
+        action_name(frame.action),
+        frame.reward,
+        frame.acc_reward,
+        frame.done
+    );
+    println!(
+        "Position: {:?} | Energy: {} | Backpack: {:?} | Weather: {} {}",
+        frame.position, frame.energy, frame.backpack, frame.weather, frame.time
+    );
+    println!();
+
+    // world with the robot position
+    for (row_i, row) in recording.world.iter().enumerate() {
+        let line: String = row
+            .iter()
+            .enumerate()
+            .map(|(col_i, tile)| {
+                if (row_i, col_i) == frame.position {
+                    '@'
+                } else {
+                    tile_symbol(tile)
+                }
+            })
+            .collect();
+        println!("{}", line);
+    }
+    println!();
+
+    // robot view
+    println!("Robot view:");
+    for row in frame.view.iter() {
+        let line: String = row
+            .iter()
+            .map(|tile| match tile {
+                None => ' ',
+                Some(tile) => tile_symbol(tile),
+            })
+            .collect();
+        println!("  {}", line);
+    }
+    println!();
+    render_flags(frame);
+}
+
+fn render_flags(frame: &Frame) {
+    println!(
+        "{:<16}{:^5}{:^5}{:^5}{:^5}",
+        "", "Up", "Right", "Down", "Left"
+    );
+    for (name, flags) in [
+        ("Danger", frame.danger),
+        ("Coin Direction", frame.coin_dir),
+        ("Coin Adjacency", frame.coin_adj),
+        ("Bank Direction", frame.bank_dir),
+        ("Bank Adjacency", frame.bank_adj),
+    ] {
+        println!(
+            "{:<16}{:^5}{:^5}{:^5}{:^5}",
+            name, flags[0], flags[1], flags[2], flags[3]
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use crate::utils::runs::{RunConfig, RunDir};
+use crate::utils::stats::print_summary_table;
+use std::collections::BTreeSet;
+
+// one line per run with the best evaluation score of its models
+pub fn list_runs() {
+    println!(
+        "|{:_^18}|{:_^26}|{:_^14}|{:_^8}|{:_^12}|{:_^12}|",
+        "Run", "Algorithm", "Commit", "Models", "Best Score", "Eval Mean"
+    );
+    for run in RunDir::all() {
+        let config = run.config();
+        let manifests = run.manifests();
+        let best = manifests
+            .iter()
+            .map(|(_, manifest)| manifest.eval_score)
+            .fold(None, |best: Option<f64>, score| {
+                Some(best.map_or(score, |best| best.max(score)))
+            });
+        println!(
+            "|{:^18}|{:^26}|{:^14}|{:^8}|{:^12}|{:^12}|",
+            run.id(),
+            config
+                .as_ref()
+                .map_or("-".to_string(), |config| config.algorithm.clone()),
+            config
+                .as_ref()
+                .map_or("-".to_string(), |config| short_commit(config)),
+            manifests.len(),
+            best.map_or("-".to_string(), |best| format!("{:.3}", best)),
+            eval_mean(&run).map_or("-".to_string(), |mean| format!("{:.3}", mean)),
+        );
+    }
+}
+
+// config, saved models and last evaluation of a run
+pub fn show_run(id: String) {
+    let run = RunDir::open(&id);
+    println!("Run {}", run.id());
+    if let Some(config) = run.config() {
+        println!("Algorithm: {}", config.algorithm);
+        println!("Commit: {}", config.git_commit.as_deref().unwrap_or("-"));
+        println!("Maps: {}", config.maps.join(", "));
+        println!("Hyperparameters:");
+        for (key, value) in &config.hyperparameters {
+            println!("  {}: {}", key, value);
+        }
+    }
+    println!("Models:");
+    for (name, manifest) in run.manifests() {
+        println!(
+            "  {}: map {}, episode {}, evaluation {:.3}",
+            name, manifest.map, manifest.episode, manifest.eval_score
+        );
+    }
+    if let Some(summary) = run.summary() {
+        print_summary_table(&summary);
+    }
+}
+
+// hyperparameters that differ between the runs and their evaluation results side by side
+pub fn compare_runs(ids: Vec<String>) {
+    let runs = ids.iter().map(|id| RunDir::open(id)).collect::<Vec<_>>();
+    let configs = runs.iter().map(|run| run.config()).collect::<Vec<_>>();
+    let row = |name: &str, values: Vec<String>| {
+        print!("|{:^24}|", name);
+        for value in values {
+            print!("{:^26}|", value);
+        }
+        println!();
+    };
+
+    print!("|{:_^24}|", "");
+    for run in &runs {
+        print!("{:_^26}|", run.id());
+    }
+    println!();
+    row(
+        "algorithm",
+        configs
+            .iter()
+            .map(|config| {
+                config
+                    .as_ref()
+                    .map_or("-".to_string(), |c| c.algorithm.clone())
+            })
+            .collect(),
+    );
+    row(
+        "commit",
+        configs
+            .iter()
+            .map(|config| config.as_ref().map_or("-".to_string(), short_commit))
+            .collect(),
+    );
+    let keys = configs
+        .iter()
+        .flatten()
+        .flat_map(|config| config.hyperparameters.keys().cloned())
+        .collect::<BTreeSet<String>>();
+    for key in keys {
+        let values = configs
+            .iter()
+            .map(|config| {
+                config
+                    .as_ref()
+                    .and_then(|config| config.hyperparameters.get(&key).cloned())
+                    .unwrap_or("-".to_string())
+            })
+            .collect::<Vec<_>>();
+        if values.iter().any(|value| value != &values[0]) {
+            row(&key, values);
+        }
+    }
+
+    let manifests = runs.iter().map(|run| run.manifests()).collect::<Vec<_>>();
+    let summaries = runs.iter().map(|run| run.summary()).collect::<Vec<_>>();
+    let maps = manifests
+        .iter()
+        .flatten()
+        .map(|(_, manifest)| manifest.map.clone())
+        .chain(
+            summaries
+                .iter()
+                .flatten()
+                .flatten()
+                .map(|(map, _)| map.clone()),
+        )
+        .collect::<BTreeSet<String>>();
+    for map in maps {
+        row(
+            &format!("best {}", map),
+            manifests
+                .iter()
+                .map(|manifests| {
+                    manifests
+                        .iter()
+                        .find(|(_, manifest)| manifest.map == map)
+                        .map_or("-".to_string(), |(_, manifest)| {
+                            format!("{:.3}", manifest.eval_score)
+                        })
+                })
+                .collect(),
+        );
+        row(
+            &format!("eval {}", map),
+            summaries
+                .iter()
+                .map(|summary| {
+                    summary
+                        .iter()
+                        .flatten()
+                        .find(|(name, _)| name == &map)
+                        .map_or("-".to_string(), |(_, summary)| {
+                            format!(
+                                "{:.3} ± {:.3} ({:.0}%)",
+                                summary.mean_return,
+                                summary.std_return,
+                                summary.success_rate * 100.
+                            )
+                        })
+                })
+                .collect(),
+        );
+    }
+}
+
+fn short_commit(config: &RunConfig) -> String {
+    config
+        .git_commit
+        .as_deref()
+        .map_or("-".to_string(), |commit| commit.chars().take(12).collect())
+}
+
+// mean return of the last evaluation over all the maps
+fn eval_mean(run: &RunDir) -> Option<f64> {
+    let summary = run.summary()?;
+    if summary.is_empty() {
+        return None;
+    }
+    Some(
+        summary
+            .iter()
+            .map(|(_, summary)| summary.mean_return)
+            .sum::<f64>()
+            / summary.len() as f64,
+    )
+}
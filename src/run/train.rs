@@ -1,69 +1,68 @@
 use crate::gym::GymEnv;
-use crate::model::{Actor, Agent, Critic, Noise};
+use crate::model::{
+    manifest_path, Actor, Agent, Critic, Curriculum, EarlyStopping, Learner, Manifest, Noise,
+    RecurrentActor, RecurrentAgent, RecurrentCritic, TrainMetrics,
+};
 use crate::utils::consts::{
-    ACTOR_LAYERS, BATCH, COINS_DESTROYED_TARGET, COINS_STORED_TARGET, CRITIC_LAYERS, EP, GAMMA,
-    LR_A, LR_C, MAPS, MAP_BASE, MAX_EP, MEM_DIM, MODEL_BASE, MU, N_WORKERS, SIGMA, TAU, THETA,
-    TRAIN_ITERATIONS, TRAIN_LOG, TRAIN_PLOT, TRAIN_STATE,
+    ACTOR_GRAD_CLIP, ACTOR_SPEC, BATCH, BURN_IN, CHECKPOINTS_KEPT, CHECKPOINT_BASE,
+    CRITIC_GRAD_CLIP, CRITIC_HEAD, CRITIC_LOSS, CRITIC_SPEC, CURRICULUM, CURRICULUM_THRESHOLD,
+    CURRICULUM_WINDOW, DOMAIN_RANDOMIZATION, EARLY_STOP_MIN_EPISODES, EARLY_STOP_PATIENCE,
+    EARLY_STOP_SUCCESS_RATE, EP, EVAL_EVERY, GAMMA, LR_A, LR_A_SCHEDULER, LR_C, LR_C_SCHEDULER,
+    MAPS, MAX_EP, MEM_DIM, MODEL_BASE, MU, N_WORKERS, RECURRENT, RESET_OPTIONS, RNN_HIDDEN,
+    SEQ_LEN, SIGMA, TASK, TAU, THETA, TRAIN_EVAL_EPISODES, TRAIN_ITERATIONS, TRAIN_LOG, TRAIN_PLOT,
+    TRAIN_PROGRESS, TRAIN_STATE,
 };
-use crate::utils::functions::plot;
+use crate::utils::functions::{greedy_action, new_episode, plot, remove_model, run_episode};
+use crate::utils::runs::RunDir;
+use crate::utils::stats::EvalSummary;
+use crate::world::generator::ProceduralGenerator;
+use crate::world::WorldSource;
+use std::collections::VecDeque;
 use std::fs::File;
 use std::io::Write;
 use std::thread::spawn;
 use tch::Kind::Float;
-use worldgen_unwrap::public::WorldgeneratorUnwrap;
 
 pub fn train() {
+    let run = RunDir::create();
+    println!("Training run {}", run.id());
     let mut handles = vec![];
     (0..N_WORKERS).for_each(|worker| {
+        let run = run.clone();
         handles.push(spawn(move || {
-            let generator = WorldgeneratorUnwrap::init(
-                false,
-                Some(format!("{}/{}", MAP_BASE, MAPS[worker]).into()),
-            );
-            let mut env = GymEnv::new(generator, COINS_DESTROYED_TARGET, COINS_STORED_TARGET);
+            let mut curriculum = CURRICULUM.map(|stages| {
+                Curriculum::new(stages, CURRICULUM_WINDOW, CURRICULUM_THRESHOLD)
+            });
+            let (source, mut map_name) = match (&curriculum, DOMAIN_RANDOMIZATION) {
+                (Some(curriculum), _) => (curriculum.source(worker as u64), curriculum.name()),
+                (None, None) => (WorldSource::map(MAPS[worker]), MAPS[worker].to_string()),
+                (None, Some(randomization)) => (
+                    WorldSource::Procedural(ProceduralGenerator::randomized(
+                        randomization,
+                        worker as u64,
+                    )),
+                    "randomized".to_string(),
+                ),
+            };
+            let mut env = GymEnv::new(source, TASK.collect_goal, TASK.deposit_goal);
+            env.set_options(RESET_OPTIONS, worker as u64);
             let observation_space = env.observation_space().iter().product::<i64>() as usize;
             let action_space = env.action_space() as usize;
-            let actor = Actor::new(
-                observation_space,
-                action_space,
-                LR_A,
-                &ACTOR_LAYERS,
-                format!("{}_{}.pt", MODEL_BASE, worker),
-            );
-            let mut actor_target = Actor::new(
+            let mut agent = create_agent(
                 observation_space,
                 action_space,
-                LR_A,
-                &ACTOR_LAYERS,
-                format!("{}_{}.pt", MODEL_BASE, worker),
-            );
-            actor_target.import(&actor);
-            let critic = Critic::new(observation_space, action_space, LR_C, &CRITIC_LAYERS);
-            let mut critic_target =
-                Critic::new(observation_space, action_space, LR_C, &CRITIC_LAYERS);
-            critic_target.import(&critic);
-            let noise = Noise::new(THETA, SIGMA, MU, action_space as i64);
-            let mut agent = Agent::new(
-                actor,
-                actor_target,
-                critic,
-                critic_target,
-                noise,
-                MEM_DIM,
-                true,
-                GAMMA,
-                TAU,
+                run.file(&format!("{}_{}.pt", MODEL_BASE, worker)),
             );
 
             // data for plotting and saving
-            let mut log_file = File::create(format!("{}_{}.log", TRAIN_LOG, worker)).unwrap();
+            let mut log_file = File::create(run.file(&format!("{}_{}.log", TRAIN_LOG, worker))).unwrap();
             let mut state_log_file =
-                File::create(format!("{}_{}.log", TRAIN_STATE, worker)).unwrap();
+                File::create(run.file(&format!("{}_{}.log", TRAIN_STATE, worker))).unwrap();
             log_file
                 .write_all(
                     format!(
-                        "|{:_^11}|{:_^8}|{:_^12}|{:_^7}|{:_^12}|\n",
-                        "Iteration", "Action", "Reward", "Done", "Acc. Reward"
+                        "|{:_^11}|{:_^8}|{:_^12}|{:_^7}|{:_^12}|{:_^8}|\n",
+                        "Iteration", "Action", "Reward", "Done", "Acc. Reward", "Energy"
                     )
                     .as_bytes(),
                 )
@@ -81,22 +80,58 @@ pub fn train() {
                     .as_bytes(),
                 )
                 .unwrap();
+            let mut progress_file =
+                File::create(run.file(&format!("{}_{}.log", TRAIN_PROGRESS, worker))).unwrap();
+            progress_file
+                .write_all(
+                    format!(
+                        "|{:_^9}|{:_^7}|{:_^12}|{:_^11}|{:_^11}|{:_^12}|{:_^12}|{:_^11}|{:_^11}|{:_^12}|{:_^12}|{:_^12}|{:_^12}|{:_^12}|\n",
+                        "Episode",
+                        "Stage",
+                        "Acc. Reward",
+                        "LR Actor",
+                        "LR Critic",
+                        "Critic Loss",
+                        "Actor Loss",
+                        "Critic Grad",
+                        "Actor Grad",
+                        "Mean Q",
+                        "Max Q",
+                        "TD Mean",
+                        "TD Std",
+                        "TD Max"
+                    )
+                    .as_bytes(),
+                )
+                .unwrap();
 
             let mut log_data = vec![];
             let mut state_log_data = vec![];
             let mut best_acc_rw = f64::MIN;
+            let mut best_eval_rw = f64::MIN;
+            let mut checkpoints = VecDeque::new();
+            let mut early_stopping = EarlyStopping::new(
+                EARLY_STOP_PATIENCE,
+                EARLY_STOP_SUCCESS_RATE,
+                EARLY_STOP_MIN_EPISODES,
+            );
             let mut memory = vec![];
             let mut min_rw = f64::MAX;
             let mut max_rw = f64::MIN;
 
             for episode in 0..EP {
+                let lr_a = LR_A_SCHEDULER.lr(LR_A, episode);
+                let lr_c = LR_C_SCHEDULER.lr(LR_C, episode);
+                agent.set_lr(lr_a, lr_c);
+
                 let mut ep_log_data = vec![];
                 let mut ep_state_log_data = vec![];
-                let mut obs = env.reset();
+                let mut obs = new_episode(&mut env, agent.as_mut());
                 let mut acc_rw = 0.;
                 let mut ep_min_rw = f64::MAX;
                 let mut ep_max_rw = f64::MIN;
                 let mut ep_memory = vec![];
+                let mut completed = false;
 
                 for i in 0..MAX_EP {
                     // get an action given an observation
@@ -108,12 +143,12 @@ pub fn train() {
                     acc_rw += step.reward;
                     // remember the reward, min and max for plotting
 
+                    let state = env.state();
                     let log = format!(
-                        "|{:^11}|{:^8}|{:^12.3}|{:^7}|{:^12.3}|\n",
-                        i, action, step.reward, step.done, acc_rw
+                        "|{:^11}|{:^8}|{:^12.3}|{:^7}|{:^12.3}|{:^8}|\n",
+                        i, action, step.reward, step.done, acc_rw, state.energy
                     );
                     ep_log_data.push(log);
-                    let state = env.state();
                     let state_log = format!(
                         "| {:?} | {:?} | {:?} | {:?} | {:?} |\n",
                         state.danger,
@@ -134,16 +169,24 @@ pub fn train() {
                     // store the transition into the replay memory
                     agent.remember(&obs, &actions, &step.reward.into(), &step.obs);
                     if step.done {
-                        println!("T: {worker} completed the task");
+                        completed = !state.starved;
+                        if completed {
+                            println!("T: {worker} completed the task");
+                        } else {
+                            println!("T: {worker} ran out of energy");
+                        }
                         break;
                     }
                     // update the observation
                     obs = step.obs;
                 }
 
-                println!("T: {worker}, episode: {episode} with a total reward of {acc_rw:.4}");
+                println!(
+                    "T: {worker}, episode: {episode} with a total reward of {acc_rw:.4}, start: {:?}",
+                    env.info()
+                );
 
-                // save if the episode is better than the previous best
+                // keep the logs of the best training episode
                 if acc_rw > best_acc_rw {
                     log_data = ep_log_data;
                     state_log_data = ep_state_log_data;
@@ -151,13 +194,97 @@ pub fn train() {
                     min_rw = ep_min_rw;
                     max_rw = ep_max_rw;
                     memory = ep_memory;
-                    // save the actor model
-                    println!("T: {worker}, found new best");
-                    agent.save()
                 }
 
-                for _ in 0..TRAIN_ITERATIONS {
-                    agent.train(BATCH);
+                let metrics = (0..TRAIN_ITERATIONS)
+                    .filter_map(|_| agent.train(BATCH))
+                    .collect::<Vec<_>>();
+                let metrics = TrainMetrics::mean(&metrics).unwrap_or_default();
+                let progress = format!(
+                    "|{:^9}|{:^7}|{:^12.3}|{:^11.3e}|{:^11.3e}|{:^12.3}|{:^12.3}|{:^11.3}|{:^11.3}|{:^12.3}|{:^12.3}|{:^12.3}|{:^12.3}|{:^12.3}|\n",
+                    episode,
+                    curriculum.as_ref().map_or(0, |curriculum| curriculum.stage()),
+                    acc_rw,
+                    lr_a,
+                    lr_c,
+                    metrics.critic_loss,
+                    metrics.actor_loss,
+                    metrics.critic_grad_norm,
+                    metrics.actor_grad_norm,
+                    metrics.mean_q,
+                    metrics.max_q,
+                    metrics.td_error_mean,
+                    metrics.td_error_std,
+                    metrics.td_error_max
+                );
+                progress_file.write_all(progress.as_bytes()).unwrap();
+
+                // harder worlds once the current ones are mastered, the best model and the early
+                // stopping start over since the returns of different stages aren't comparable
+                if let Some(curriculum) = curriculum.as_mut() {
+                    if curriculum.update(completed) {
+                        map_name = curriculum.name();
+                        println!(
+                            "T: {worker}, episode: {episode} moving to stage {} ({map_name})",
+                            curriculum.stage()
+                        );
+                        env.set_generator(curriculum.source(worker as u64));
+                        best_eval_rw = f64::MIN;
+                        early_stopping = EarlyStopping::new(
+                            EARLY_STOP_PATIENCE,
+                            EARLY_STOP_SUCCESS_RATE,
+                            EARLY_STOP_MIN_EPISODES,
+                        );
+                    }
+                }
+
+                // evaluate without noise, the best model is chosen on the evaluation return
+                if (episode + 1) % EVAL_EVERY == 0 {
+                    let stats = (0..TRAIN_EVAL_EPISODES)
+                        .map(|_| {
+                            let obs = new_episode(&mut env, agent.as_mut());
+                            run_episode(
+                                &mut env,
+                                obs,
+                                MAX_EP,
+                                |obs| greedy_action(&agent.greedy_actions(obs)),
+                                |_, _, _| {},
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    let summary = EvalSummary::new(&stats);
+                    println!(
+                        "T: {worker}, episode: {episode} evaluated with a return of {:.4}",
+                        summary.mean_return
+                    );
+
+                    let manifest = Manifest::new(&map_name, episode, summary.mean_return);
+                    let last = run.file(&format!("{}_{}_last.pt", CHECKPOINT_BASE, worker));
+                    agent.save_to(&last);
+                    manifest.save(&manifest_path(&last));
+                    let checkpoint =
+                        run.file(&format!("{}_{}_ep{}.pt", CHECKPOINT_BASE, worker, episode));
+                    agent.save_to(&checkpoint);
+                    manifest.save(&manifest_path(&checkpoint));
+                    checkpoints.push_back(checkpoint);
+                    if checkpoints.len() > CHECKPOINTS_KEPT {
+                        remove_model(&checkpoints.pop_front().unwrap());
+                    }
+                    if summary.mean_return > best_eval_rw {
+                        best_eval_rw = summary.mean_return;
+                        println!("T: {worker}, found new best");
+                        agent.save();
+                        manifest.save(&manifest_path(
+                            &run.file(&format!("{}_{}.pt", MODEL_BASE, worker)),
+                        ));
+                    }
+                    let last_stage = curriculum
+                        .as_ref()
+                        .map_or(true, |curriculum| curriculum.is_last());
+                    if early_stopping.update(&summary) && last_stage {
+                        println!("T: {worker}, stopping early at episode {episode}");
+                        break;
+                    }
                 }
             }
             // log the data
@@ -168,7 +295,7 @@ pub fn train() {
 
             // plot the best episode
             plot(
-                format!("{}_{}.png", TRAIN_PLOT, worker),
+                run.file(&format!("{}_{}.png", TRAIN_PLOT, worker)),
                 memory,
                 min_rw,
                 max_rw,
@@ -179,3 +306,113 @@ pub fn train() {
         handle.join().unwrap();
     }
 }
+
+fn create_agent(
+    observation_space: usize,
+    action_space: usize,
+    save_path: String,
+) -> Box<dyn Learner> {
+    let noise = Noise::new(THETA, SIGMA, MU, action_space as i64);
+    match RECURRENT {
+        None => {
+            let actor = Actor::new(
+                observation_space,
+                action_space,
+                LR_A,
+                &ACTOR_SPEC,
+                save_path.clone(),
+            );
+            let mut actor_target = Actor::new(
+                observation_space,
+                action_space,
+                LR_A,
+                &ACTOR_SPEC,
+                save_path,
+            );
+            actor_target.import(&actor);
+            let critic = Critic::new(
+                observation_space,
+                action_space,
+                LR_C,
+                &CRITIC_SPEC,
+                CRITIC_HEAD,
+            );
+            let mut critic_target = Critic::new(
+                observation_space,
+                action_space,
+                LR_C,
+                &CRITIC_SPEC,
+                CRITIC_HEAD,
+            );
+            critic_target.import(&critic);
+            Box::new(Agent::new(
+                actor,
+                actor_target,
+                critic,
+                critic_target,
+                noise,
+                MEM_DIM,
+                true,
+                GAMMA,
+                TAU,
+                CRITIC_LOSS,
+                ACTOR_GRAD_CLIP,
+                CRITIC_GRAD_CLIP,
+            ))
+        }
+        Some(kind) => {
+            let actor = RecurrentActor::new(
+                observation_space,
+                action_space,
+                LR_A,
+                RNN_HIDDEN,
+                kind,
+                &ACTOR_SPEC,
+                save_path.clone(),
+            );
+            let mut actor_target = RecurrentActor::new(
+                observation_space,
+                action_space,
+                LR_A,
+                RNN_HIDDEN,
+                kind,
+                &ACTOR_SPEC,
+                save_path,
+            );
+            actor_target.import(&actor);
+            let critic = RecurrentCritic::new(
+                observation_space,
+                action_space,
+                LR_C,
+                RNN_HIDDEN,
+                kind,
+                &CRITIC_SPEC,
+            );
+            let mut critic_target = RecurrentCritic::new(
+                observation_space,
+                action_space,
+                LR_C,
+                RNN_HIDDEN,
+                kind,
+                &CRITIC_SPEC,
+            );
+            critic_target.import(&critic);
+            Box::new(RecurrentAgent::new(
+                actor,
+                actor_target,
+                critic,
+                critic_target,
+                noise,
+                MEM_DIM,
+                true,
+                GAMMA,
+                TAU,
+                CRITIC_LOSS,
+                ACTOR_GRAD_CLIP,
+                CRITIC_GRAD_CLIP,
+                BURN_IN,
+                SEQ_LEN,
+            ))
+        }
+    }
+}
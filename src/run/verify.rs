@@ -0,0 +1,78 @@
+use crate::export::mlp::MlpPolicy;
+use crate::gym::GymEnv;
+use crate::model::load_model;
+use crate::utils::consts::{EXPORT_BASE, MAPS, MAX_EP, MODEL_BASE, N_WORKERS, TASK};
+use crate::utils::functions::{greedy_action, new_episode, run_episode};
+use crate::utils::runs::RunDir;
+use crate::world::WorldSource;
+use std::process::exit;
+use std::thread::spawn;
+use tch::{Device, Kind, Tensor};
+
+// compare the exported MLP policies with the TorchScript actors on the observations
+// met by the TorchScript actors during `episodes` greedy episodes
+pub fn verify(episodes: usize, run: Option<String>) {
+    let run = RunDir::resolve(run);
+    let mut handles = vec![];
+    (0..N_WORKERS).for_each(|worker| {
+        let run = run.clone();
+        handles.push(spawn(move || {
+            let mut env = GymEnv::new(
+                WorldSource::map(MAPS[worker]),
+                TASK.collect_goal,
+                TASK.deposit_goal,
+            );
+            let mut model = load_model(&run.file(&format!("{}_{}.pt", MODEL_BASE, worker)));
+            assert!(
+                !model.is_recurrent(),
+                "Recurrent actors can't be exported, there is nothing to verify"
+            );
+            let policy = MlpPolicy::load(&run.file(&format!("{}_{}.json", EXPORT_BASE, worker)));
+
+            let mut observations = 0;
+            let mut mismatches = 0;
+            let mut max_diff = 0f32;
+            for _ in 0..episodes {
+                let obs = new_episode(&mut env, &mut model);
+                run_episode(
+                    &mut env,
+                    obs,
+                    MAX_EP,
+                    |obs| {
+                        let outputs = model.forward(obs);
+                        let action = greedy_action(&outputs);
+                        let features = to_vec(obs);
+                        let mlp_outputs = policy.forward(&features);
+                        observations += 1;
+                        if policy.act(&features) as i64 != action {
+                            mismatches += 1;
+                        }
+                        for (a, b) in to_vec(&outputs).iter().zip(&mlp_outputs) {
+                            max_diff = max_diff.max((a - b).abs());
+                        }
+                        action
+                    },
+                    |_, _, _| {},
+                );
+            }
+            println!(
+                "T: {worker}, {observations} observations, {mismatches} different actions, max output difference {max_diff:e}"
+            );
+            mismatches == 0
+        }));
+    });
+    let passed = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .all(|passed| passed);
+    if passed {
+        println!("The exported policies match the TorchScript actors");
+    } else {
+        println!("The exported policies don't match the TorchScript actors");
+        exit(1);
+    }
+}
+
+fn to_vec(tensor: &Tensor) -> Vec<f32> {
+    Vec::<f32>::try_from(tensor.to_device(Device::Cpu).to_kind(Kind::Float)).unwrap()
+}
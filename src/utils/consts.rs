@@ -1,7 +1,41 @@
+use crate::gym::events::EventWeights;
+use crate::gym::options::ResetOptions;
+use crate::gym::task::Task;
+use crate::model::{
+    Activation, CriticHead, CriticLoss, InitScheme, LrScheduler, NetworkSpec, Norm, RnnKind, Stage,
+};
+use crate::world::generator::Randomization;
+use robotics_lib::world::environmental_conditions::WeatherType;
 use robotics_lib::world::tile::Content;
 
-pub const N_ACTIONS: i64 = 16;
+// Extra action that only lets a tick pass, so that the energy can regenerate,
+// WAIT is its index and only exists if WAIT_ACTION is set
+pub const WAIT_ACTION: bool = false;
+pub const WAIT: i64 = 16;
+pub const N_ACTIONS: i64 = WAIT + WAIT_ACTION as i64;
 pub const N_OBSERVATIONS: i64 = 20;
+// Egocentric grid observation appended to the observations, None disables it,
+// the size must be odd so that the robot is in the center
+pub const GRID_OBSERVATION: Option<usize> = None;
+pub const GRID_CHANNELS: usize = 5;
+// Weather (one-hot over WEATHERS) and time of day (sine and cosine of the minute of the day)
+// appended to the observations before the grid, robotics_lib doesn't expose the forecast
+pub const WEATHER_OBSERVATION: bool = false;
+pub const WEATHERS: [WeatherType; 5] = [
+    WeatherType::Sunny,
+    WeatherType::Rainy,
+    WeatherType::Foggy,
+    WeatherType::TropicalMonsoon,
+    WeatherType::TrentinoSnow,
+];
+pub const N_SKY_FEATURES: i64 = WEATHERS.len() as i64 + 2;
+// Offsets of the k nearest known contents to collect and deposits (with their remaining capacity)
+// appended to the observations before the grid, None disables them
+pub const NEAREST_TARGETS: Option<usize> = None;
+// Features of every nearest content to collect and deposit
+pub const NEAREST_COLLECT_FIELDS: [&str; 3] = ["di", "dj", "known"];
+pub const NEAREST_DEPOSIT_FIELDS: [&str; 4] = ["di", "dj", "known", "capacity"];
+pub const N_TILE_TYPES: f64 = 11.;
 pub const MEM_DIM: usize = 100_000;
 pub const PLOT_WIDTH: u32 = 1024;
 pub const PLOT_HEIGHT: u32 = 768;
@@ -10,9 +44,33 @@ pub const FONT_SIZE: u32 = 20;
 pub const LABEL_AREA_SIZE: u32 = 40;
 pub const X_LABELS: usize = 20;
 pub const Y_LABELS: usize = 30;
-pub const CONTENT_TARGETS: [Content; 2] = [Content::Coin(0), Content::Bank(0..0)];
+// Content to collect and where to deposit it, e.g. trees in crates or garbage in bins
+// (maps made with the editor, the generated maps only have coins and banks)
+pub const TASK: Task = Task {
+    collect: Content::Coin(0),
+    deposit: Content::Bank(0..0),
+    collect_goal: 30,
+    deposit_goal: 20,
+};
 // Rewards fn
 pub const REWARD_FOR_ILLEGAL_ACTION: f64 = -1000.;
+pub const WAIT_REWARD: f64 = -5.;
+// put into a deposit that can't hold anything else
+pub const FULL_DEPOSIT_REWARD: f64 = -500.;
+// Reward terms for the events of every action, added to the reward of the action
+pub const EVENT_REWARDS: EventWeights = EventWeights {
+    energy_recharged: 0.,
+    energy_consumed: 0.,
+    moves: 0.,
+    tiles_updated: 0.,
+    added_to_backpack: 0.,
+    removed_from_backpack: 0.,
+    weather_changes: 0.,
+};
+// End the episode after STARVED_PATIENCE consecutive ticks in which the robot can't do anything
+// (every direction dangerous, nothing to destroy or put and not enough energy to scan)
+pub const STARVED_PATIENCE: usize = 10;
+pub const STARVED_REWARD: f64 = -500.;
 pub const RW_NO_SCAN: f64 = -900.;
 pub const PERCENTAGE_ENERGY_RESERVED_FOR_SCANNING: f64 = 0.04;
 pub const LIM_F_COINS: f64 = 2.0;
@@ -28,23 +86,113 @@ pub const MAPS: [&str; 4] = [
     "coin_bank_adj_map.bin",
     "test_normal_map.bin",
 ];
-pub const MODEL_BASE: &str = "src/save/models/model";
 pub const MAP_BASE: &str = "src/save/maps";
-pub const EVAL_LOG: &str = "src/save/eval/log";
-pub const EVAL_STATE: &str = "src/save/eval/state";
-pub const TRAIN_LOG: &str = "src/save/train/log";
-pub const TRAIN_STATE: &str = "src/save/train/state";
-pub const EVAL_PLOT: &str = "src/save/eval/plot";
-pub const TRAIN_PLOT: &str = "src/save/train/plot";
+// keep the maps in memory instead of reading them at every reset
+pub const CACHE_WORLDS: bool = true;
+pub const BENCH_RESETS: usize = 1000;
+// procedural maps
+pub const MAP_SIZE: usize = 30;
+pub const MAP_COIN_DENSITY: f64 = 0.03;
+pub const MAP_BANK_DENSITY: f64 = 0.01;
+pub const MAP_DANGER_DENSITY: f64 = 0.05;
+pub const MAX_COINS_PER_TILE: usize = 5;
+pub const BANK_CAPACITY: usize = 50;
+pub const MAX_ELEVATION: usize = 4;
+pub const TERRAIN_SMOOTHING: usize = 3;
+pub const MAP_ATTEMPTS: usize = 100;
+pub const WEATHER_TICK_MINUTES: u8 = 15;
+pub const START_HOUR: u8 = 12;
+pub const WEATHER_FORECAST_LEN: usize = 4;
+// Train on a new random world at every episode instead of the MAPS (e.g. Some(RANDOMIZATION)),
+// the evaluations still use the MAPS
+pub const DOMAIN_RANDOMIZATION: Option<Randomization> = None;
+pub const RANDOMIZATION: Randomization = Randomization {
+    size: (20, 40),
+    coin_density: (0.01, 0.05),
+    bank_density: (0.005, 0.02),
+    danger_density: (0., 0.1),
+    weather: true,
+};
+// Randomize the start of every training episode (spawn, initial energy and goals within the
+// inclusive ranges, weather), the evaluations use EVAL_RESET_OPTIONS
+pub const RESET_OPTIONS: ResetOptions = ResetOptions {
+    random_spawn: false,
+    energy: None,
+    coins_destroyed_goal: None,
+    coins_stored_goal: None,
+    random_weather: false,
+};
+// Start of the evaluation episodes, drawn from the seeds of `eval` and `matrix`
+pub const EVAL_RESET_OPTIONS: ResetOptions = ResetOptions {
+    random_spawn: true,
+    energy: None,
+    coins_destroyed_goal: None,
+    coins_stored_goal: None,
+    random_weather: false,
+};
+// Train every worker from the easiest stage, moving on when the success rate of the last
+// CURRICULUM_WINDOW training episodes reaches CURRICULUM_THRESHOLD (e.g. Some(&CURRICULUM_STAGES)),
+// it replaces the MAPS and DOMAIN_RANDOMIZATION during training
+pub const CURRICULUM: Option<&[Stage]> = None;
+pub const CURRICULUM_STAGES: [Stage; 4] = [
+    Stage::Map("coin_bank_adj_map.bin"),
+    Stage::Map("coin_bank_1_away_map.bin"),
+    Stage::Map("adj_danger_map.bin"),
+    Stage::Map("test_normal_map.bin"),
+];
+pub const CURRICULUM_WINDOW: usize = 20;
+pub const CURRICULUM_THRESHOLD: f64 = 0.8;
+// every training run gets its own folder, runs made before the folders existed live in SAVE_DIR
+pub const SAVE_DIR: &str = "src/save";
+pub const RUNS_DIR: &str = "src/save/runs";
+// paths relative to the folder of a run
+pub const RUN_CONFIG: &str = "config.json";
+pub const MODEL_BASE: &str = "models/model";
+pub const MODEL_DIR: &str = "models";
+pub const CHECKPOINT_DIR: &str = "models/checkpoints";
+pub const CHECKPOINT_BASE: &str = "models/checkpoints/model";
+pub const EVAL_DIR: &str = "eval";
+pub const EVAL_LOG: &str = "eval/log";
+pub const EVAL_STATE: &str = "eval/state";
+pub const EVAL_REPLAY: &str = "eval/replay";
+pub const EVAL_SUMMARY: &str = "eval/summary.json";
+pub const EVAL_WEATHER: &str = "eval/weather.json";
+pub const EVAL_MATRIX: &str = "eval/matrix.json";
+pub const EXPORT_DIR: &str = "export";
+pub const EXPORT_BASE: &str = "export/model";
+pub const TRAIN_DIR: &str = "train";
+pub const TRAIN_LOG: &str = "train/log";
+pub const TRAIN_STATE: &str = "train/state";
+pub const EVAL_PLOT: &str = "eval/plot";
+pub const TRAIN_PLOT: &str = "train/plot";
+pub const TRAIN_PROGRESS: &str = "train/progress";
 pub const EP: usize = 3000;
 pub const MAX_EP: usize = 100;
 pub const BATCH: usize = 20;
 pub const ACTOR_LAYERS: [i64; 2] = [1000, 600];
 pub const CRITIC_LAYERS: [i64; 2] = [1000, 600];
+pub const ACTOR_SPEC: NetworkSpec = NetworkSpec {
+    layers: &ACTOR_LAYERS,
+    activation: Activation::Relu,
+    norm: Norm::None,
+    dropout: 0.,
+    init: InitScheme::Default,
+    final_init: None,
+    action_layer: 0,
+    encoder: None,
+};
+pub const CRITIC_SPEC: NetworkSpec = NetworkSpec {
+    layers: &CRITIC_LAYERS,
+    activation: Activation::Relu,
+    norm: Norm::None,
+    dropout: 0.,
+    init: InitScheme::Default,
+    final_init: None,
+    action_layer: 0,
+    encoder: None,
+};
 pub const LR_A: f64 = 0.001;
 pub const LR_C: f64 = 0.004;
-pub const COINS_STORED_TARGET: usize = 20;
-pub const COINS_DESTROYED_TARGET: usize = 30;
 pub const N_WORKERS: usize = 4;
 pub const THETA: f64 = 0.15;
 pub const SIGMA: f64 = 0.2;
@@ -52,3 +200,31 @@ pub const MU: f64 = 0.0;
 pub const GAMMA: f64 = 0.99;
 pub const TAU: f64 = 0.001;
 pub const TRAIN_ITERATIONS: usize = 100;
+pub const EVAL_EPISODES: usize = 10;
+// Evaluation during training
+pub const EVAL_EVERY: usize = 50;
+pub const TRAIN_EVAL_EPISODES: usize = 1;
+pub const CHECKPOINTS_KEPT: usize = 5;
+// Early stopping, None disables the criterion (e.g. Some(10) and Some(1.0)),
+// the success rate is only trusted on evaluations of at least EARLY_STOP_MIN_EPISODES episodes
+pub const EARLY_STOP_PATIENCE: Option<usize> = None;
+pub const EARLY_STOP_SUCCESS_RATE: Option<f64> = None;
+pub const EARLY_STOP_MIN_EPISODES: usize = 10;
+// Learning rate schedulers
+pub const LR_A_SCHEDULER: LrScheduler = LrScheduler::Constant;
+pub const LR_C_SCHEDULER: LrScheduler = LrScheduler::Constant;
+// Recurrent actor and critic, None uses the feed-forward ones,
+// ACTOR_SPEC and CRITIC_SPEC then describe the layers after the recurrent one
+pub const RECURRENT: Option<RnnKind> = None;
+pub const RNN_HIDDEN: i64 = 256;
+pub const BURN_IN: usize = 10;
+pub const SEQ_LEN: usize = 20;
+// Critic head, the critic loss is ignored by the categorical head
+pub const CRITIC_HEAD: CriticHead = CriticHead::Scalar;
+// Critic loss and global gradient norm clipping, None disables the clipping,
+// Huber { delta: 1.0 } and Some(1.0) are recommended when the rewards are large
+pub const CRITIC_LOSS: CriticLoss = CriticLoss::Mse;
+pub const ACTOR_GRAD_CLIP: Option<f64> = None;
+pub const CRITIC_GRAD_CLIP: Option<f64> = None;
+// Replay viewer
+pub const REPLAY_CLEAR: &str = "\x1B[2J\x1B[1;1H";
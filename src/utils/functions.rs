@@ -2,35 +2,122 @@ use ghost_journey_journal::JourneyJournal;
 use plotters::prelude::{
     BitMapBackend, ChartBuilder, IntoDrawingArea, IntoFont, LineSeries, BLACK, WHITE,
 };
-use robotics_lib::interface::{look_at_sky, robot_view, Direction};
+use robotics_lib::interface::{look_at_sky, robot_map, robot_view, Direction};
 use robotics_lib::runner::Runnable;
 use robotics_lib::utils::calculate_cost_go_with_environment;
-use robotics_lib::world::tile::{Content, Tile};
+use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
+use robotics_lib::world::tile::{Content, Tile, TileType};
 use robotics_lib::world::World;
 use std::cmp::Ordering;
-use tch::nn::{linear, seq, Path, Sequential, VarStore};
-use tch::no_grad;
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use std::fs::{read_dir, remove_file};
+use std::path::PathBuf;
+use tch::nn::{
+    batch_norm1d, layer_norm, linear, seq_t, Init, LinearConfig, Path, SequentialT, VarStore,
+};
+use tch::Kind::Float;
+use tch::{no_grad, Tensor};
 
 use crate::gym::robot::GymRobot;
-use crate::model::Critic;
+use crate::gym::state::{weather_name, State};
+use crate::gym::{GymEnv, Step};
+use crate::model::{manifest_path, Episodic, InitScheme, NetworkSpec, Norm};
 use crate::utils::consts::{
-    COEFFICIENT_X_SCAN, CONTENT_TARGETS, FONT_SIZE, LABEL_AREA_SIZE, LIM_F_SCAN, LOG_BASE_SCAN,
-    PLOT_FONT, PLOT_HEIGHT, PLOT_WIDTH, RW_NO_SCAN, X_LABELS, Y_LABELS,
+    COEFFICIENT_X_SCAN, FONT_SIZE, GRID_CHANNELS, GRID_OBSERVATION, LABEL_AREA_SIZE, LIM_F_SCAN,
+    LOG_BASE_SCAN, NEAREST_COLLECT_FIELDS, NEAREST_DEPOSIT_FIELDS, NEAREST_TARGETS, N_OBSERVATIONS,
+    N_SKY_FEATURES, N_TILE_TYPES, PLOT_FONT, PLOT_HEIGHT, PLOT_WIDTH, RW_NO_SCAN, TASK, WAIT,
+    WAIT_ACTION, WEATHERS, WEATHER_OBSERVATION, X_LABELS, Y_LABELS,
 };
+use crate::utils::stats::EpisodeStats;
 
-// weighted sum of two trainable variables
+// weighted sum of all the variables, the batch norm running statistics included
 pub fn update_vs(dst: &mut VarStore, src: &VarStore, tau: f64) {
+    let src = src.variables();
     no_grad(|| {
-        for (dest, src) in dst
-            .trainable_variables()
-            .iter_mut()
-            .zip(src.trainable_variables().iter())
-        {
-            dest.copy_(&(tau * src + (1.0 - tau) * &*dest));
+        for (name, mut dest) in dst.variables() {
+            dest.copy_(&(tau * &src[&name] + (1.0 - tau) * &dest));
         }
     })
 }
 
+// index of the highest scoring action
+pub fn greedy_action(actions: &Tensor) -> i64 {
+    actions.softmax(-1, Float).argmax(-1, true).int64_value(&[])
+}
+
+// reset the environment together with the state the policy keeps across the steps,
+// so that nothing leaks from one episode into the next
+pub fn new_episode<P: Episodic + ?Sized>(env: &mut GymEnv, policy: &mut P) -> Tensor {
+    policy.reset();
+    env.reset()
+}
+
+// play an episode from the `obs` returned by `new_episode` choosing the actions with `policy`,
+// `on_step` is called after every action
+pub fn run_episode<P, F>(
+    env: &mut GymEnv,
+    mut obs: Tensor,
+    max_steps: usize,
+    mut policy: P,
+    mut on_step: F,
+) -> EpisodeStats
+where
+    P: FnMut(&Tensor) -> i64,
+    F: FnMut(usize, &Step, &State),
+{
+    let mut stats = EpisodeStats {
+        ret: 0.,
+        steps: 0,
+        success: false,
+        coins_destroyed: 0,
+        coins_stored: 0,
+        illegal_actions: 0,
+        full_deposits: 0,
+        starved: false,
+        weather: String::new(),
+    };
+    let mut weathers = BTreeMap::new();
+    for i in 0..max_steps {
+        let action = policy(&obs);
+        let step = env.step(action);
+        let state = env.state();
+        stats.ret += step.reward;
+        stats.steps += 1;
+        stats.coins_destroyed = state.coins_destroyed;
+        stats.coins_stored = state.coins_stored;
+        if state.illegal {
+            stats.illegal_actions += 1;
+        }
+        if state.full_deposit {
+            stats.full_deposits += 1;
+        }
+        *weathers.entry(weather_name(&state)).or_insert(0) += 1;
+        on_step(i, &step, &state);
+        if step.done {
+            stats.success = !state.starved;
+            stats.starved = state.starved;
+            break;
+        }
+        obs = step.obs;
+    }
+    if let Some((weather, _)) = weathers.into_iter().max_by_key(|&(_, steps)| steps) {
+        stats.weather = weather;
+    }
+    stats
+}
+
+// global norm of the gradients of the trainable variables
+pub fn grad_norm(vs: &VarStore) -> f64 {
+    vs.trainable_variables()
+        .iter()
+        .map(|var| var.grad())
+        .filter(|grad| grad.defined())
+        .map(|grad| grad.pow_tensor_scalar(2).sum(Float).double_value(&[]))
+        .sum::<f64>()
+        .sqrt()
+}
+
 pub fn plot(path: String, memory: Vec<f64>, min_rw: f64, max_rw: f64) {
     // plot background
     let root = BitMapBackend::new(&path, (PLOT_WIDTH, PLOT_HEIGHT)).into_drawing_area();
@@ -71,14 +158,14 @@ pub fn update_closest(robot: &mut GymRobot, world: &mut World) {
     robot.state.borrow_mut().coin_adj = [0.0; 4];
     robot.state.borrow_mut().bank_adj = [0.0; 4];
 
-    let mut journal = JourneyJournal::new(&[], &CONTENT_TARGETS, false);
+    let mut journal = JourneyJournal::new(&[], &TASK.targets(), false);
 
-    // get the closest coin and bank
+    // get the closest content to collect and deposit
     let closest_coin: Option<(usize, usize)> = journal
-        .contents_closest_coords(&Content::Coin(0), robot, world)
+        .contents_closest_coords(&TASK.collect.to_default(), robot, world)
         .unwrap();
     let closest_bank: Option<(usize, usize)> = journal
-        .contents_closest_coords(&Content::Bank(0..0), robot, world)
+        .contents_closest_coords(&TASK.deposit.to_default(), robot, world)
         .unwrap();
 
     // update the coin and bank direction and adjacency
@@ -234,6 +321,122 @@ fn update_danger_adj(robot: &mut GymRobot, i: usize, j: usize) {
     }
 }
 
+// egocentric grid of the known world centered on the robot, flattened channel by channel:
+// tile type, coin, bank, danger (unwalkable or outside the world) and visited
+pub fn update_grid(robot: &GymRobot, world: &World, size: usize) {
+    let map = robot_map(world).unwrap_or_default();
+    let robot_i = robot.get_coordinate().get_row() as i64;
+    let robot_j = robot.get_coordinate().get_col() as i64;
+    let half = (size / 2) as i64;
+    let cells = size * size;
+    let mut grid = vec![0.; GRID_CHANNELS * cells];
+    for di in 0..size {
+        for dj in 0..size {
+            let cell = di * size + dj;
+            let (i, j) = (robot_i + di as i64 - half, robot_j + dj as i64 - half);
+            let tile = if i < 0 || j < 0 {
+                None
+            } else {
+                map.get(i as usize).and_then(|row| row.get(j as usize))
+            };
+            match tile {
+                // outside the world
+                None => grid[3 * cells + cell] = 1.,
+                // not discovered yet
+                Some(None) => {}
+                Some(Some(tile)) => {
+                    grid[cell] = (tile_type_index(&tile.tile_type) + 1) as f64 / N_TILE_TYPES;
+                    if TASK.is_collect(&tile.content) {
+                        grid[cells + cell] = 1.;
+                    } else if TASK.is_deposit(&tile.content) {
+                        grid[2 * cells + cell] = 1.;
+                    }
+                    if !tile.tile_type.properties().walk() {
+                        grid[3 * cells + cell] = 1.;
+                    }
+                    if robot.visited.contains(&(i as usize, j as usize)) {
+                        grid[4 * cells + cell] = 1.;
+                    }
+                }
+            }
+        }
+    }
+    robot.state.borrow_mut().grid = grid;
+}
+
+// offsets of the k nearest known contents to collect and deposits, scaled by the side of the world,
+// with a flag for the slots that are filled and the remaining capacity of the deposits
+pub fn update_nearest(robot: &GymRobot, world: &World, k: usize) {
+    let map = robot_map(world).unwrap_or_default();
+    let robot_i = robot.get_coordinate().get_row();
+    let robot_j = robot.get_coordinate().get_col();
+    let side = map.len().max(1) as f64;
+    let mut collect = vec![];
+    let mut deposit = vec![];
+    for (i, row) in map.iter().enumerate() {
+        for (j, tile) in row.iter().enumerate() {
+            if let Some(tile) = tile {
+                let distance = dist_from_robot(robot_i, robot_j, i, j);
+                if TASK.is_collect(&tile.content) {
+                    collect.push((distance, i, j, None));
+                } else if TASK.is_deposit(&tile.content) {
+                    deposit.push((distance, i, j, capacity_left(&tile.content)));
+                }
+            }
+        }
+    }
+
+    let mut features = vec![];
+    for (mut targets, width, is_deposit) in [
+        (collect, NEAREST_COLLECT_FIELDS.len(), false),
+        (deposit, NEAREST_DEPOSIT_FIELDS.len(), true),
+    ] {
+        targets.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for slot in 0..k {
+            match targets.get(slot) {
+                None => features.extend(vec![0.; width]),
+                Some(&(_, i, j, capacity)) => {
+                    features.push((i as f64 - robot_i as f64) / side);
+                    features.push((j as f64 - robot_j as f64) / side);
+                    features.push(1.);
+                    if is_deposit {
+                        features.push(
+                            capacity.map_or(1., |(left, total)| left as f64 / total.max(1) as f64),
+                        );
+                    }
+                }
+            }
+        }
+    }
+    robot.state.borrow_mut().nearest = features;
+}
+
+// amount a deposit can still hold and its total capacity, None if it isn't limited
+pub fn capacity_left(content: &Content) -> Option<(usize, usize)> {
+    match content {
+        Content::Bank(range) | Content::Crate(range) | Content::Bin(range) => {
+            Some((range.end.saturating_sub(range.start), range.end))
+        }
+        _ => None,
+    }
+}
+
+fn tile_type_index(tile_type: &TileType) -> usize {
+    match tile_type {
+        TileType::DeepWater => 0,
+        TileType::ShallowWater => 1,
+        TileType::Sand => 2,
+        TileType::Grass => 3,
+        TileType::Street => 4,
+        TileType::Hill => 5,
+        TileType::Mountain => 6,
+        TileType::Snow => 7,
+        TileType::Lava => 8,
+        TileType::Teleport(_) => 9,
+        TileType::Wall => 10,
+    }
+}
+
 fn dist_from_robot(r_i: usize, r_j: usize, i: usize, j: usize) -> f64 {
     ((r_i as f64 - i as f64).powi(2) + (r_j as f64 - j as f64).powi(2)).sqrt()
 }
@@ -245,13 +448,13 @@ pub fn scan_reward(
     world: &mut World,
 ) -> f64 {
     let (mut n_coins, mut n_banks) = (0, 0);
-    let mut journal = JourneyJournal::new(&[], &CONTENT_TARGETS, false);
+    let mut journal = JourneyJournal::new(&[], &TASK.targets(), false);
 
     let coins = journal
-        .contents_list_coords(&Content::Coin(0), world)
+        .contents_list_coords(&TASK.collect.to_default(), world)
         .unwrap();
     let banks = journal
-        .contents_list_coords(&Content::Bank(0..0), world)
+        .contents_list_coords(&TASK.deposit.to_default(), world)
         .unwrap();
 
     let robot_i = robot.get_coordinate().get_row() as i64;
@@ -269,15 +472,11 @@ pub fn scan_reward(
                 (robot_i + relative_i) as usize,
                 (robot_j + relative_j) as usize,
             );
-            if let Content::Coin(_) = tile.content {
-                if !coins.contains(&coord) {
-                    n_coins += 1;
-                }
+            if TASK.is_collect(&tile.content) && !coins.contains(&coord) {
+                n_coins += 1;
             }
-            if let Content::Bank(_) = tile.content {
-                if !banks.contains(&coord) {
-                    n_banks += 1;
-                }
+            if TASK.is_deposit(&tile.content) && !banks.contains(&coord) {
+                n_banks += 1;
             }
         }
     }
@@ -293,46 +492,248 @@ pub fn reward_fn(x: f64, coefficient_x: f64, log_base: f64, lim: f64) -> f64 {
     ((coefficient_x * x + 1.0).log(log_base) + lim * x) / -x
 }
 
-pub fn create_network(
-    p: &Path,
-    observation_space: i64,
-    action_space: i64,
-    hidden_layers: &[i64],
-    critic: bool,
-) -> Sequential {
-    let mut network = seq()
-        .add(linear(
-            p / "in",
-            {
-                if critic {
-                    observation_space + action_space
-                } else {
-                    observation_space
-                }
+fn layer_config(init: &InitScheme, fan_in: i64) -> LinearConfig {
+    match *init {
+        InitScheme::Default => Default::default(),
+        InitScheme::Orthogonal { gain } => LinearConfig {
+            ws_init: Init::Orthogonal { gain },
+            bs_init: Some(Init::Const(0.)),
+            bias: true,
+        },
+        InitScheme::FanInUniform => {
+            let bound = 1. / (fan_in as f64).sqrt();
+            LinearConfig {
+                ws_init: Init::Uniform {
+                    lo: -bound,
+                    up: bound,
+                },
+                bs_init: Some(Init::Uniform {
+                    lo: -bound,
+                    up: bound,
+                }),
+                bias: true,
+            }
+        }
+    }
+}
+
+// hidden layers with the given widths, the rest of the spec decides how they are built
+pub fn create_hidden(p: &Path, input: i64, layers: &[i64], spec: &NetworkSpec) -> SequentialT {
+    let mut network = seq_t();
+    let mut in_dim = input;
+    for (i, &out_dim) in layers.iter().enumerate() {
+        let name = if i == 0 {
+            "in".to_string()
+        } else {
+            format!("hd{}", i - 1)
+        };
+        network = network.add(linear(
+            p / &name,
+            in_dim,
+            out_dim,
+            layer_config(&spec.init, in_dim),
+        ));
+        network = match spec.norm {
+            Norm::None => network,
+            Norm::Layer => network.add(layer_norm(
+                p / format!("{}_norm", name),
+                vec![out_dim],
+                Default::default(),
+            )),
+            Norm::Batch => network.add(batch_norm1d(
+                p / format!("{}_norm", name),
+                out_dim,
+                Default::default(),
+            )),
+        };
+        let activation = spec.activation;
+        network = network.add_fn(move |xs| activation.apply(xs));
+        if spec.dropout > 0. {
+            let dropout = spec.dropout;
+            network = network.add_fn_t(move |xs, train| xs.dropout(dropout, train));
+        }
+        in_dim = out_dim;
+    }
+    network
+}
+
+pub fn create_network(p: &Path, input: i64, output: i64, spec: &NetworkSpec) -> SequentialT {
+    let network = create_hidden(p, input, spec.layers, spec);
+    let last = spec.layers.last().copied().unwrap_or(input);
+    let config = match spec.final_init {
+        Some(bound) => LinearConfig {
+            ws_init: Init::Uniform {
+                lo: -bound,
+                up: bound,
             },
-            hidden_layers[0],
-            Default::default(),
-        ))
-        .add_fn(|xs| xs.relu());
-    for (i, (&x, &y)) in hidden_layers
+            bs_init: Some(Init::Uniform {
+                lo: -bound,
+                up: bound,
+            }),
+            bias: true,
+        },
+        None => layer_config(&spec.init, last),
+    };
+    network.add(linear(p / "out", last, output, config))
+}
+
+// size of the observations built by `State::build`
+pub fn observation_size() -> i64 {
+    flat_size() + GRID_OBSERVATION.map_or(0, |size| (GRID_CHANNELS * size * size) as i64)
+}
+
+// features before the grid
+pub fn flat_size() -> i64 {
+    let sky = if WEATHER_OBSERVATION {
+        N_SKY_FEATURES
+    } else {
+        0
+    };
+    let nearest = NEAREST_COLLECT_FIELDS.len() + NEAREST_DEPOSIT_FIELDS.len();
+    N_OBSERVATIONS + sky + NEAREST_TARGETS.map_or(0, |k| (nearest * k) as i64)
+}
+
+// one-hot weather and time of day on the unit circle, so that midnight is next to 23:59
+pub fn sky_features(conditions: &EnvironmentalConditions) -> Vec<f64> {
+    let weather = conditions.get_weather_condition();
+    let minutes = conditions
+        .get_time_of_day_string()
+        .split(':')
+        .map(|part| part.trim().parse::<f64>().unwrap_or(0.))
+        .fold(0., |acc, part| acc * 60. + part);
+    let angle = 2. * PI * minutes / (24. * 60.);
+    WEATHERS
         .iter()
-        .zip(hidden_layers.iter().skip(1))
-        .enumerate()
-    {
-        network = network
-            .add(linear(p / format!("hd{}", i), x, y, Default::default()))
-            .add_fn(|xs| xs.relu());
+        .map(|w| if *w == weather { 1. } else { 0. })
+        .chain([angle.sin(), angle.cos()])
+        .collect()
+}
+
+// names of the features built by `State::build`
+pub fn observation_layout() -> Vec<String> {
+    let directions = ["up", "right", "down", "left"];
+    let (collect, deposit) = TASK.names();
+    let groups = [
+        "danger".to_string(),
+        format!("{}_dir", collect),
+        format!("{}_dir", deposit),
+        format!("{}_adj", collect),
+        format!("{}_adj", deposit),
+    ];
+    let mut layout = vec![];
+    for group in groups {
+        layout.extend(directions.iter().map(|dir| format!("{}_{}", group, dir)));
     }
-    network.add(linear(
-        p / "out",
-        *hidden_layers.last().unwrap(),
-        {
-            if critic {
-                1
-            } else {
-                action_space
+    if WEATHER_OBSERVATION {
+        layout.extend(
+            WEATHERS
+                .iter()
+                .map(|weather| format!("weather_{:?}", weather).to_lowercase()),
+        );
+        layout.extend(["time_sin".to_string(), "time_cos".to_string()]);
+    }
+    if let Some(k) = NEAREST_TARGETS {
+        for (name, fields) in [
+            (&collect, &NEAREST_COLLECT_FIELDS[..]),
+            (&deposit, &NEAREST_DEPOSIT_FIELDS[..]),
+        ] {
+            for slot in 0..k {
+                layout.extend(
+                    fields
+                        .iter()
+                        .map(|field| format!("nearest_{}_{}_{}", name, slot, field)),
+                );
             }
-        },
-        Default::default(),
-    ))
+        }
+    }
+    if let Some(size) = GRID_OBSERVATION {
+        let channels = [
+            "tile_type",
+            collect.as_str(),
+            deposit.as_str(),
+            "danger",
+            "visited",
+        ];
+        assert_eq!(channels.len(), GRID_CHANNELS);
+        for channel in channels {
+            for i in 0..size {
+                layout.extend((0..size).map(|j| format!("grid_{}_{}_{}", channel, i, j)));
+            }
+        }
+    }
+    layout
+}
+
+// weights saved next to a traced model
+pub fn weights_path(model_path: &str) -> String {
+    format!("{}.safetensors", model_path.trim_end_matches(".pt"))
+}
+
+// remove a saved model together with its weights and manifest, if present
+pub fn remove_model(model_path: &str) {
+    remove_file(model_path).unwrap();
+    let _ = remove_file(weights_path(model_path));
+    let _ = remove_file(manifest_path(model_path));
+}
+
+// single character representation of a tile used by the terminal replay
+pub fn tile_symbol(tile: &Tile) -> char {
+    if TASK.is_collect(&tile.content) {
+        return 'c';
+    }
+    if TASK.is_deposit(&tile.content) {
+        return 'B';
+    }
+    match tile.tile_type {
+        TileType::DeepWater => '~',
+        TileType::ShallowWater => '-',
+        TileType::Sand => ':',
+        TileType::Grass => '.',
+        TileType::Street => '=',
+        TileType::Hill => 'n',
+        TileType::Mountain => '^',
+        TileType::Snow => '*',
+        TileType::Lava => '!',
+        TileType::Teleport(_) => 'T',
+        TileType::Wall => '#',
+    }
+}
+
+// human readable name of an action, following the encoding used by `GymRobot::step`
+pub fn action_name(action: i64) -> String {
+    if action < 0 {
+        return "Reset".to_string();
+    }
+    if WAIT_ACTION && action == WAIT {
+        return "Wait".to_string();
+    }
+    let dir = match action % 4 {
+        0 => "Up",
+        1 => "Right",
+        2 => "Down",
+        _ => "Left",
+    };
+    let kind = match action / 4 {
+        0 => "Move",
+        1 => "Destroy",
+        2 => "Put",
+        _ => "Scan",
+    };
+    format!("{} {}", kind, dir)
+}
+
+// files of `dir` with the given extension, sorted by name
+pub fn list_files(dir: &str, extension: &str) -> Vec<PathBuf> {
+    let mut files = read_dir(dir)
+        .unwrap()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map_or(false, |ext| ext == extension))
+        .collect::<Vec<PathBuf>>();
+    files.sort();
+    files
+}
+
+// name of a file without its directory
+pub fn file_name(path: &std::path::Path) -> String {
+    path.file_name().unwrap().to_string_lossy().to_string()
 }
@@ -1,2 +1,4 @@
 pub mod consts;
 pub mod functions;
+pub mod runs;
+pub mod stats;
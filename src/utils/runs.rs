@@ -0,0 +1,158 @@
+use crate::model::{algorithm, git_commit, hyperparameters, manifest_path, Manifest};
+use crate::utils::consts::{
+    CHECKPOINT_DIR, CURRICULUM, DOMAIN_RANDOMIZATION, EVAL_DIR, EVAL_SUMMARY, EXPORT_DIR, MAPS,
+    MODEL_DIR, RUNS_DIR, RUN_CONFIG, SAVE_DIR, TRAIN_DIR,
+};
+use crate::utils::functions::{file_name, list_files};
+use crate::utils::stats::EvalSummary;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{create_dir, create_dir_all, read_dir, File};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// # Run configuration
+///
+/// Saved in the folder of a run when the training starts
+///
+/// - `id`: name of the run folder
+/// - `created`: unix time of the start of the training
+/// - `algorithm`: training algorithm
+/// - `hyperparameters`: training configuration, values formatted as text
+/// - `git_commit`: commit of the code of the run
+/// - `maps`: training map of every worker
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RunConfig {
+    pub id: String,
+    pub created: u64,
+    pub algorithm: String,
+    pub hyperparameters: BTreeMap<String, String>,
+    pub git_commit: Option<String>,
+    pub maps: Vec<String>,
+}
+
+/// # Run folder
+///
+/// Holds the config, models, checkpoints, logs, plots, evaluations and exports of a training run,
+/// the files are addressed with the relative paths of the consts
+#[derive(Clone, Debug)]
+pub struct RunDir {
+    id: String,
+    root: PathBuf,
+}
+
+impl RunDir {
+    // new run with the current configuration
+    pub fn create() -> Self {
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        // trainings started in the same second get a suffix, creating the folder claims the id
+        create_dir_all(RUNS_DIR).unwrap();
+        let id = (0..)
+            .map(|n| match n {
+                0 => format!("run_{}", created),
+                n => format!("run_{}_{}", created, n),
+            })
+            .find(|id| match create_dir(PathBuf::from(RUNS_DIR).join(id)) {
+                Ok(()) => true,
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => false,
+                Err(e) => panic!("Can't create the run folder: {}", e),
+            })
+            .unwrap();
+        let run = Self {
+            root: PathBuf::from(RUNS_DIR).join(&id),
+            id: id.clone(),
+        };
+        for dir in [CHECKPOINT_DIR, TRAIN_DIR, EVAL_DIR, EXPORT_DIR] {
+            create_dir_all(run.file(dir)).unwrap();
+        }
+        let config = RunConfig {
+            id,
+            created,
+            algorithm: algorithm(),
+            hyperparameters: hyperparameters(),
+            git_commit: git_commit(),
+            maps: match (CURRICULUM, DOMAIN_RANDOMIZATION) {
+                (Some(stages), _) => stages.iter().map(|stage| stage.name()).collect(),
+                (None, None) => MAPS.iter().map(|map| map.to_string()).collect(),
+                (None, Some(_)) => vec!["randomized".to_string()],
+            },
+        };
+        serde_json::to_writer_pretty(File::create(run.file(RUN_CONFIG)).unwrap(), &config).unwrap();
+        run
+    }
+
+    pub fn open(id: &str) -> Self {
+        let root = PathBuf::from(RUNS_DIR).join(id);
+        assert!(root.is_dir(), "There is no run {}", id);
+        Self {
+            id: id.to_string(),
+            root,
+        }
+    }
+
+    // runs sorted from the oldest
+    pub fn all() -> Vec<Self> {
+        let Ok(entries) = read_dir(RUNS_DIR) else {
+            return vec![];
+        };
+        let mut ids = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect::<Vec<String>>();
+        ids.sort();
+        ids.iter().map(|id| Self::open(id)).collect()
+    }
+
+    // the given run, by default the latest one or the save folder if there are no runs
+    pub fn resolve(id: Option<String>) -> Self {
+        match id {
+            Some(id) => Self::open(&id),
+            None => Self::all().pop().unwrap_or_else(|| Self {
+                id: "legacy".to_string(),
+                root: PathBuf::from(SAVE_DIR),
+            }),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    // path of a file of the run
+    pub fn file(&self, relative: &str) -> String {
+        self.root.join(relative).to_string_lossy().to_string()
+    }
+
+    pub fn config(&self) -> Option<RunConfig> {
+        File::open(self.file(RUN_CONFIG))
+            .ok()
+            .map(|file| serde_json::from_reader(file).unwrap())
+    }
+
+    // manifests of the best models, by model file name
+    pub fn manifests(&self) -> Vec<(String, Manifest)> {
+        let dir = self.file(MODEL_DIR);
+        if !PathBuf::from(&dir).is_dir() {
+            return vec![];
+        }
+        list_files(&dir, "pt")
+            .iter()
+            .filter_map(|path| {
+                Manifest::load(&manifest_path(path.to_str().unwrap()))
+                    .map(|manifest| (file_name(path), manifest))
+            })
+            .collect()
+    }
+
+    // last evaluation summary written by `eval`
+    pub fn summary(&self) -> Option<Vec<(String, EvalSummary)>> {
+        File::open(self.file(EVAL_SUMMARY))
+            .ok()
+            .map(|file| serde_json::from_reader(file).unwrap())
+    }
+}
@@ -0,0 +1,268 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// # Episode statistics
+///
+/// - `ret`: accumulated reward
+/// - `steps`: number of actions performed
+/// - `success`: whether the task was completed
+/// - `illegal_actions`: number of actions rewarded with `REWARD_FOR_ILLEGAL_ACTION`
+/// - `full_deposits`: puts into a deposit without capacity left
+/// - `starved`: whether the episode ended because the robot ran out of energy
+/// - `weather`: weather of most of the steps
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EpisodeStats {
+    pub ret: f64,
+    pub steps: usize,
+    pub success: bool,
+    pub coins_destroyed: usize,
+    pub coins_stored: usize,
+    pub illegal_actions: usize,
+    #[serde(default)]
+    pub full_deposits: usize,
+    #[serde(default)]
+    pub starved: bool,
+    #[serde(default)]
+    pub weather: String,
+}
+
+/// # Evaluation summary
+///
+/// Aggregated statistics over a set of episodes,
+/// `avg_steps_to_goal` only considers the successful episodes
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EvalSummary {
+    pub episodes: usize,
+    pub mean_return: f64,
+    pub std_return: f64,
+    pub median_return: f64,
+    pub min_return: f64,
+    pub max_return: f64,
+    pub success_rate: f64,
+    pub avg_steps_to_goal: Option<f64>,
+    pub avg_coins_destroyed: f64,
+    pub avg_coins_stored: f64,
+    pub illegal_action_rate: f64,
+    #[serde(default)]
+    pub starved_rate: f64,
+    #[serde(default)]
+    pub full_deposit_rate: f64,
+}
+
+impl EvalSummary {
+    pub fn new(episodes: &[EpisodeStats]) -> Self {
+        let n = episodes.len().max(1) as f64;
+        let mut returns = episodes.iter().map(|e| e.ret).collect::<Vec<f64>>();
+        returns.sort_by(|a, b| a.total_cmp(b));
+
+        let mean_return = returns.iter().sum::<f64>() / n;
+        let std_return = (returns
+            .iter()
+            .map(|r| (r - mean_return).powi(2))
+            .sum::<f64>()
+            / n)
+            .sqrt();
+        let median_return = match returns.len() {
+            0 => 0.,
+            len if len % 2 == 0 => (returns[len / 2 - 1] + returns[len / 2]) / 2.,
+            len => returns[len / 2],
+        };
+
+        let successes = episodes.iter().filter(|e| e.success).collect::<Vec<_>>();
+        let avg_steps_to_goal = if successes.is_empty() {
+            None
+        } else {
+            Some(successes.iter().map(|e| e.steps as f64).sum::<f64>() / successes.len() as f64)
+        };
+        let steps = episodes.iter().map(|e| e.steps).sum::<usize>().max(1) as f64;
+
+        Self {
+            episodes: episodes.len(),
+            mean_return,
+            std_return,
+            median_return,
+            min_return: returns.first().copied().unwrap_or(0.),
+            max_return: returns.last().copied().unwrap_or(0.),
+            success_rate: successes.len() as f64 / n,
+            avg_steps_to_goal,
+            avg_coins_destroyed: episodes
+                .iter()
+                .map(|e| e.coins_destroyed as f64)
+                .sum::<f64>()
+                / n,
+            avg_coins_stored: episodes.iter().map(|e| e.coins_stored as f64).sum::<f64>() / n,
+            illegal_action_rate: episodes.iter().map(|e| e.illegal_actions).sum::<usize>() as f64
+                / steps,
+            starved_rate: episodes.iter().filter(|e| e.starved).count() as f64 / n,
+            full_deposit_rate: episodes.iter().map(|e| e.full_deposits).sum::<usize>() as f64
+                / steps,
+        }
+    }
+}
+
+// summary of the episodes of every weather
+pub fn weather_summaries(episodes: &[EpisodeStats]) -> Vec<(String, EvalSummary)> {
+    let mut weathers = BTreeMap::new();
+    for episode in episodes {
+        weathers
+            .entry(episode.weather.clone())
+            .or_insert(vec![])
+            .push(episode.clone());
+    }
+    weathers
+        .into_iter()
+        .map(|(weather, episodes)| (weather, EvalSummary::new(&episodes)))
+        .collect()
+}
+
+pub fn print_summary_table(rows: &[(String, EvalSummary)]) {
+    println!(
+        "|{:_^26}|{:_^6}|{:_^12}|{:_^10}|{:_^12}|{:_^12}|{:_^12}|{:_^9}|{:_^8}|{:_^8}|{:_^8}|{:_^9}|{:_^9}|{:_^10}|",
+        "Map",
+        "Ep.",
+        "Mean",
+        "Std",
+        "Median",
+        "Min",
+        "Max",
+        "Success",
+        "Steps",
+        "Dstr.",
+        "Stored",
+        "Illegal",
+        "Starved",
+        "Full dep."
+    );
+    for (name, summary) in rows {
+        println!(
+            "|{:^26}|{:^6}|{:^12.3}|{:^10.3}|{:^12.3}|{:^12.3}|{:^12.3}|{:^9.2}|{:^8}|{:^8.2}|{:^8.2}|{:^9.2}|{:^9.2}|{:^10.3}|",
+            name,
+            summary.episodes,
+            summary.mean_return,
+            summary.std_return,
+            summary.median_return,
+            summary.min_return,
+            summary.max_return,
+            summary.success_rate,
+            summary
+                .avg_steps_to_goal
+                .map_or("-".to_string(), |s| format!("{:.1}", s)),
+            summary.avg_coins_destroyed,
+            summary.avg_coins_stored,
+            summary.illegal_action_rate,
+            summary.starved_rate,
+            summary.full_deposit_rate,
+        );
+    }
+}
+
+/// # Cross evaluation entry
+///
+/// Result of evaluating `model` on `map`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MatrixEntry {
+    pub model: String,
+    pub map: String,
+    pub summary: EvalSummary,
+}
+
+// print a model x map table of success rates and one of mean returns
+pub fn print_matrix(entries: &[MatrixEntry], models: &[String], maps: &[String]) {
+    for (title, value) in [
+        (
+            "Success rate",
+            (|s: &EvalSummary| s.success_rate) as fn(&EvalSummary) -> f64,
+        ),
+        ("Mean return", |s: &EvalSummary| s.mean_return),
+    ] {
+        println!("{}", title);
+        print!("|{:_^20}|", "Model \\ Map");
+        for map in maps {
+            print!("{:_^26}|", map);
+        }
+        println!();
+        for model in models {
+            print!("|{:^20}|", model);
+            for map in maps {
+                match entries.iter().find(|e| &e.model == model && &e.map == map) {
+                    Some(entry) => print!("{:^26.3}|", value(&entry.summary)),
+                    None => print!("{:^26}|", "-"),
+                }
+            }
+            println!();
+        }
+        println!();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn episode(ret: f64, steps: usize, success: bool, weather: &str) -> EpisodeStats {
+        EpisodeStats {
+            ret,
+            steps,
+            success,
+            coins_destroyed: 2,
+            coins_stored: 1,
+            illegal_actions: 1,
+            full_deposits: 0,
+            starved: !success,
+            weather: weather.to_string(),
+        }
+    }
+
+    #[test]
+    fn single_episode() {
+        let summary = EvalSummary::new(&[episode(3., 10, true, "Sunny")]);
+        assert_eq!(summary.episodes, 1);
+        assert_eq!(summary.mean_return, 3.);
+        assert_eq!(summary.median_return, 3.);
+        assert_eq!(summary.std_return, 0.);
+        assert_eq!((summary.min_return, summary.max_return), (3., 3.));
+        assert_eq!(summary.avg_steps_to_goal, Some(10.));
+        assert_eq!(summary.illegal_action_rate, 0.1);
+    }
+
+    #[test]
+    fn no_episodes() {
+        let summary = EvalSummary::new(&[]);
+        assert_eq!(summary.episodes, 0);
+        assert_eq!(summary.mean_return, 0.);
+        assert_eq!(summary.median_return, 0.);
+        assert_eq!(summary.avg_steps_to_goal, None);
+    }
+
+    #[test]
+    fn even_episodes() {
+        let summary = EvalSummary::new(&[
+            episode(4., 10, true, "Sunny"),
+            episode(-2., 30, false, "Sunny"),
+            episode(2., 20, true, "Sunny"),
+            episode(0., 40, false, "Sunny"),
+        ]);
+        assert_eq!(summary.mean_return, 1.);
+        assert_eq!(summary.median_return, 1.);
+        assert_eq!(summary.std_return, 5f64.sqrt());
+        assert_eq!((summary.min_return, summary.max_return), (-2., 4.));
+        assert_eq!(summary.success_rate, 0.5);
+        assert_eq!(summary.starved_rate, 0.5);
+        assert_eq!(summary.avg_steps_to_goal, Some(15.));
+        assert_eq!(summary.illegal_action_rate, 0.04);
+    }
+
+    #[test]
+    fn summaries_by_weather() {
+        let summaries = weather_summaries(&[
+            episode(1., 10, true, "Sunny"),
+            episode(3., 10, true, "Rainy"),
+            episode(5., 10, false, "Sunny"),
+        ]);
+        let weathers = summaries
+            .iter()
+            .map(|(weather, summary)| (weather.as_str(), summary.episodes, summary.mean_return))
+            .collect::<Vec<_>>();
+        assert_eq!(weathers, [("Rainy", 1, 3.), ("Sunny", 2, 3.)]);
+    }
+}
@@ -0,0 +1,41 @@
+use crate::world::GeneratedWorld;
+use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
+use robotics_lib::world::tile::{Content, Tile};
+use robotics_lib::world::world_generator::Generator;
+use std::collections::HashMap;
+
+/// # World cache
+///
+/// Pristine copy of a generated world, every generation clones it
+/// instead of reading and deserializing the map again
+///
+/// - `world`: generation the copies are made from
+pub struct WorldCache {
+    world: GeneratedWorld,
+}
+
+impl WorldCache {
+    pub fn new(generator: &mut impl Generator) -> Self {
+        Self {
+            world: generator.gen(),
+        }
+    }
+
+    pub fn tiles(&self) -> &Vec<Vec<Tile>> {
+        &self.world.0
+    }
+}
+
+impl Generator for WorldCache {
+    fn gen(
+        &mut self,
+    ) -> (
+        Vec<Vec<Tile>>,
+        (usize, usize),
+        EnvironmentalConditions,
+        f32,
+        Option<HashMap<Content, f32>>,
+    ) {
+        self.world.clone()
+    }
+}
@@ -0,0 +1,293 @@
+use crate::gym::task::content_amount;
+use crate::utils::consts::{
+    BANK_CAPACITY, MAP_ATTEMPTS, MAP_BANK_DENSITY, MAP_COIN_DENSITY, MAP_DANGER_DENSITY, MAP_SIZE,
+    MAX_COINS_PER_TILE, MAX_ELEVATION, START_HOUR, TASK, TERRAIN_SMOOTHING, WEATHERS,
+    WEATHER_FORECAST_LEN, WEATHER_TICK_MINUTES,
+};
+use crate::world::map::MapStats;
+use clap::builder::RangedU64ValueParser;
+use clap::Args;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use robotics_lib::world::environmental_conditions::{EnvironmentalConditions, WeatherType};
+use robotics_lib::world::tile::{Content, Tile, TileType};
+use robotics_lib::world::world_generator::Generator;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// # Map parameters
+///
+/// - `size`: side of the square world
+/// - `coin_density`: probability of a coin on a tile that can hold it
+/// - `bank_density`: probability of a bank on a tile that can hold it
+/// - `danger_density`: probability of a deep water or lava tile
+/// - `seed`: seed of the random generator
+#[derive(Args, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct MapParams {
+    #[arg(
+        long,
+        default_value_t = MAP_SIZE,
+        value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub size: usize,
+    #[arg(long, default_value_t = MAP_COIN_DENSITY, value_parser = probability)]
+    pub coin_density: f64,
+    #[arg(long, default_value_t = MAP_BANK_DENSITY, value_parser = probability)]
+    pub bank_density: f64,
+    #[arg(long, default_value_t = MAP_DANGER_DENSITY, value_parser = probability)]
+    pub danger_density: f64,
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+}
+
+// density given on the command line, it has to be a probability
+fn probability(value: &str) -> Result<f64, String> {
+    let density = value.parse::<f64>().map_err(|e| e.to_string())?;
+    if (0. ..=1.).contains(&density) {
+        Ok(density)
+    } else {
+        Err(format!("{} is not between 0 and 1", density))
+    }
+}
+
+impl Default for MapParams {
+    fn default() -> Self {
+        Self {
+            size: MAP_SIZE,
+            coin_density: MAP_COIN_DENSITY,
+            bank_density: MAP_BANK_DENSITY,
+            danger_density: MAP_DANGER_DENSITY,
+            seed: 0,
+        }
+    }
+}
+
+/// # Domain randomization
+///
+/// Ranges the parameters of every randomized world are drawn from
+///
+/// - `size`: side of the world
+/// - `coin_density`, `bank_density`, `danger_density`: see `MapParams`
+/// - `weather`: random forecast and starting hour, otherwise always sunny at `START_HOUR`
+#[derive(Clone, Copy, Debug)]
+pub struct Randomization {
+    pub size: (usize, usize),
+    pub coin_density: (f64, f64),
+    pub bank_density: (f64, f64),
+    pub danger_density: (f64, f64),
+    pub weather: bool,
+}
+
+/// # Procedural generator
+///
+/// Every generation draws a new valid world, the sequence of worlds only depends on the seed
+///
+/// - `params`: parameters of the worlds, drawn again for every world if `randomization` is set
+/// - `randomization`: ranges of the parameters
+/// - `last`: last generated world, the worlds can't be generated again
+pub struct ProceduralGenerator {
+    params: MapParams,
+    randomization: Option<Randomization>,
+    rng: StdRng,
+    last: Vec<Vec<Tile>>,
+}
+
+impl ProceduralGenerator {
+    pub fn new(params: MapParams) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(params.seed),
+            params,
+            randomization: None,
+            last: vec![],
+        }
+    }
+
+    // new parameters and weather for every world
+    pub fn randomized(randomization: Randomization, seed: u64) -> Self {
+        Self {
+            randomization: Some(randomization),
+            ..Self::new(MapParams {
+                seed,
+                ..Default::default()
+            })
+        }
+    }
+
+    // world and spawn with at least a reachable coin and bank
+    pub fn generate(&mut self) -> (Vec<Vec<Tile>>, (usize, usize)) {
+        if let Some(randomization) = self.randomization {
+            self.params = MapParams {
+                size: self
+                    .rng
+                    .gen_range(randomization.size.0..=randomization.size.1),
+                coin_density: self
+                    .rng
+                    .gen_range(randomization.coin_density.0..=randomization.coin_density.1),
+                bank_density: self
+                    .rng
+                    .gen_range(randomization.bank_density.0..=randomization.bank_density.1),
+                danger_density: self
+                    .rng
+                    .gen_range(randomization.danger_density.0..=randomization.danger_density.1),
+                seed: self.params.seed,
+            };
+        }
+        for _ in 0..MAP_ATTEMPTS {
+            let (world, spawn) = generate_world(&self.params, &mut self.rng);
+            if MapStats::new(&world, spawn).is_valid() {
+                self.last = world.clone();
+                return (world, spawn);
+            }
+        }
+        panic!(
+            "No valid world in {} attempts, the parameters are too strict: {:?}",
+            MAP_ATTEMPTS, self.params
+        );
+    }
+
+    pub fn last_world(&self) -> &Vec<Vec<Tile>> {
+        &self.last
+    }
+}
+
+impl Generator for ProceduralGenerator {
+    fn gen(
+        &mut self,
+    ) -> (
+        Vec<Vec<Tile>>,
+        (usize, usize),
+        EnvironmentalConditions,
+        f32,
+        Option<HashMap<Content, f32>>,
+    ) {
+        let (world, spawn) = self.generate();
+        let score = max_score(&world);
+        let conditions = match self.randomization {
+            Some(randomization) if randomization.weather => random_conditions(&mut self.rng),
+            _ => default_conditions(),
+        };
+        (world, spawn, conditions, score, None)
+    }
+}
+
+pub fn default_conditions() -> EnvironmentalConditions {
+    EnvironmentalConditions::new(&[WeatherType::Sunny], WEATHER_TICK_MINUTES, START_HOUR).unwrap()
+}
+
+// forecast of random weathers starting at a random hour
+pub fn random_conditions(rng: &mut StdRng) -> EnvironmentalConditions {
+    let forecast = (0..rng.gen_range(1..=WEATHER_FORECAST_LEN))
+        .map(|_| WEATHERS.choose(rng).unwrap().clone())
+        .collect::<Vec<WeatherType>>();
+    EnvironmentalConditions::new(&forecast, WEATHER_TICK_MINUTES, rng.gen_range(0..24)).unwrap()
+}
+
+// contents to collect available in the world
+pub fn max_score(world: &[Vec<Tile>]) -> f32 {
+    world
+        .iter()
+        .flatten()
+        .filter(|tile| TASK.is_collect(&tile.content))
+        .map(|tile| content_amount(&tile.content) as f32)
+        .sum()
+}
+
+// terrain from smoothed noise: water in the valleys, grass in the plains, hills and mountains on top,
+// then danger tiles, coins and banks (the contents of TASK) scattered at random
+fn generate_world(params: &MapParams, rng: &mut StdRng) -> (Vec<Vec<Tile>>, (usize, usize)) {
+    let size = params.size;
+    let mut height = (0..size)
+        .map(|_| (0..size).map(|_| rng.gen::<f64>()).collect::<Vec<f64>>())
+        .collect::<Vec<_>>();
+    for _ in 0..TERRAIN_SMOOTHING {
+        height = smooth(&height);
+    }
+    let min = height.iter().flatten().copied().fold(f64::MAX, f64::min);
+    let max = height.iter().flatten().copied().fold(f64::MIN, f64::max);
+    let range = (max - min).max(f64::EPSILON);
+
+    let mut world = height
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&h| {
+                    let h = (h - min) / range;
+                    let tile_type = if rng.gen_bool(params.danger_density) {
+                        if h < 0.5 {
+                            TileType::DeepWater
+                        } else {
+                            TileType::Lava
+                        }
+                    } else if h < 0.15 {
+                        TileType::ShallowWater
+                    } else if h < 0.3 {
+                        TileType::Sand
+                    } else if h < 0.75 {
+                        TileType::Grass
+                    } else if h < 0.9 {
+                        TileType::Hill
+                    } else {
+                        TileType::Mountain
+                    };
+                    Tile {
+                        tile_type,
+                        content: Content::None,
+                        elevation: (h * MAX_ELEVATION as f64).round() as usize,
+                    }
+                })
+                .collect::<Vec<Tile>>()
+        })
+        .collect::<Vec<_>>();
+
+    let free = (0..size)
+        .flat_map(|i| (0..size).map(move |j| (i, j)))
+        .filter(|&(i, j)| world[i][j].tile_type.properties().walk())
+        .collect::<Vec<_>>();
+    let spawn = free.choose(rng).copied().unwrap_or((0, 0));
+    for &(i, j) in &free {
+        if (i, j) == spawn || !holds_items(&world[i][j].tile_type) {
+            continue;
+        }
+        let r = rng.gen::<f64>();
+        if r < params.coin_density {
+            world[i][j].content = TASK.collect_content(rng.gen_range(1..=MAX_COINS_PER_TILE));
+        } else if r < params.coin_density + params.bank_density {
+            world[i][j].content = TASK.deposit_content(BANK_CAPACITY);
+        }
+    }
+    (world, spawn)
+}
+
+// tiles where coins and banks are placed
+fn holds_items(tile_type: &TileType) -> bool {
+    matches!(
+        tile_type,
+        TileType::Grass | TileType::Sand | TileType::Hill | TileType::Street
+    )
+}
+
+// mean over the 3x3 neighbourhood
+fn smooth(height: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let size = height.len() as i64;
+    (0..size)
+        .map(|i| {
+            (0..size)
+                .map(|j| {
+                    let mut sum = 0.;
+                    let mut count = 0.;
+                    for di in -1..=1 {
+                        for dj in -1..=1 {
+                            let (ni, nj) = (i + di, j + dj);
+                            if ni >= 0 && nj >= 0 && ni < size && nj < size {
+                                sum += height[ni as usize][nj as usize];
+                                count += 1.;
+                            }
+                        }
+                    }
+                    sum / count
+                })
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,237 @@
+use crate::gym::task::content_amount;
+use crate::utils::consts::{MAP_ATTEMPTS, TASK};
+use crate::world::generator::{default_conditions, max_score, MapParams, ProceduralGenerator};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
+use robotics_lib::world::tile::{Content, Tile};
+use robotics_lib::world::world_generator::Generator;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::path::Path;
+
+/// # Map file
+///
+/// World generated by `init generate`, saved as JSON in the maps folder
+///
+/// - `params`: parameters the world was generated with
+/// - `world`: tiles of the world
+/// - `spawn`: starting position of the robot
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MapFile {
+    pub params: MapParams,
+    pub world: Vec<Vec<Tile>>,
+    pub spawn: (usize, usize),
+}
+
+impl MapFile {
+    pub fn generate(params: MapParams) -> Self {
+        let (world, spawn) = ProceduralGenerator::new(params).generate();
+        Self {
+            params,
+            world,
+            spawn,
+        }
+    }
+
+    pub fn save(&self, path: &Path) {
+        serde_json::to_writer(File::create(path).unwrap(), self).unwrap();
+    }
+
+    pub fn load(path: &Path) -> Self {
+        serde_json::from_reader(File::open(path).unwrap()).unwrap()
+    }
+}
+
+impl Generator for MapFile {
+    fn gen(
+        &mut self,
+    ) -> (
+        Vec<Vec<Tile>>,
+        (usize, usize),
+        EnvironmentalConditions,
+        f32,
+        Option<HashMap<Content, f32>>,
+    ) {
+        (
+            self.world.clone(),
+            self.spawn,
+            default_conditions(),
+            max_score(&self.world),
+            None,
+        )
+    }
+}
+
+/// # Map statistics
+///
+/// Coins and banks stand for the contents to collect and deposit into of `TASK`
+///
+/// - `walkable`: fraction of walkable tiles
+/// - `coins`: tiles with coins
+/// - `coin_amount`: coins over all the tiles
+/// - `banks`: tiles with banks
+/// - `danger`: tiles that can't be walked on
+/// - `reachable_coins`, `reachable_banks`: coins and banks next to a tile the robot can reach
+///   from the spawn, walking only on walkable tiles without content
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MapStats {
+    pub size: (usize, usize),
+    pub walkable: f64,
+    pub coins: usize,
+    pub coin_amount: usize,
+    pub banks: usize,
+    pub danger: usize,
+    pub reachable_coins: usize,
+    pub reachable_banks: usize,
+}
+
+impl MapStats {
+    pub fn new(world: &[Vec<Tile>], spawn: (usize, usize)) -> Self {
+        let rows = world.len();
+        let cols = world.first().map_or(0, |row| row.len());
+        let walkable = |(i, j): (usize, usize)| world[i][j].tile_type.properties().walk();
+        let free = |(i, j): (usize, usize)| {
+            walkable((i, j)) && matches!(world[i][j].content, Content::None)
+        };
+        let neighbours = |(i, j): (usize, usize)| {
+            [(-1, 0), (0, 1), (1, 0), (0, -1)]
+                .into_iter()
+                .map(move |(di, dj)| (i as i64 + di, j as i64 + dj))
+                .filter(move |&(ni, nj)| {
+                    ni >= 0 && nj >= 0 && (ni as usize) < rows && (nj as usize) < cols
+                })
+                .map(|(ni, nj)| (ni as usize, nj as usize))
+        };
+
+        // breadth first search from the spawn
+        let mut reached = vec![vec![false; cols]; rows];
+        let mut queue = VecDeque::new();
+        if spawn.0 < rows && spawn.1 < cols && walkable(spawn) {
+            reached[spawn.0][spawn.1] = true;
+            queue.push_back(spawn);
+        }
+        while let Some(position) = queue.pop_front() {
+            for next in neighbours(position) {
+                if !reached[next.0][next.1] && free(next) {
+                    reached[next.0][next.1] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let mut stats = Self {
+            size: (rows, cols),
+            walkable: 0.,
+            coins: 0,
+            coin_amount: 0,
+            banks: 0,
+            danger: 0,
+            reachable_coins: 0,
+            reachable_banks: 0,
+        };
+        let mut walkable_tiles = 0;
+        for i in 0..rows {
+            for j in 0..cols {
+                if walkable((i, j)) {
+                    walkable_tiles += 1;
+                } else {
+                    stats.danger += 1;
+                }
+                let reachable = neighbours((i, j)).any(|(ni, nj)| reached[ni][nj]);
+                let content = &world[i][j].content;
+                if TASK.is_collect(content) {
+                    stats.coins += 1;
+                    stats.coin_amount += content_amount(content);
+                    stats.reachable_coins += reachable as usize;
+                } else if TASK.is_deposit(content) {
+                    stats.banks += 1;
+                    stats.reachable_banks += reachable as usize;
+                }
+            }
+        }
+        stats.walkable = walkable_tiles as f64 / (rows * cols).max(1) as f64;
+        stats
+    }
+
+    // the task can be completed only with a reachable content to collect and deposit
+    pub fn is_valid(&self) -> bool {
+        self.reachable_coins > 0 && self.reachable_banks > 0
+    }
+}
+
+// random free tile from which a content to collect and a deposit can be reached
+pub fn random_spawn(world: &[Vec<Tile>], rng: &mut StdRng) -> Option<(usize, usize)> {
+    let mut free = world
+        .iter()
+        .enumerate()
+        .flat_map(|(i, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, tile)| {
+                    tile.tile_type.properties().walk() && matches!(tile.content, Content::None)
+                })
+                .map(move |(j, _)| (i, j))
+        })
+        .collect::<Vec<_>>();
+    free.shuffle(rng);
+    free.into_iter()
+        .take(MAP_ATTEMPTS)
+        .find(|&spawn| MapStats::new(world, spawn).is_valid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use robotics_lib::world::tile::TileType;
+
+    // `.` grass, `#` wall, `c` content to collect, `b` deposit
+    fn world(rows: &[&str]) -> Vec<Vec<Tile>> {
+        rows.iter()
+            .map(|row| {
+                row.chars()
+                    .map(|c| Tile {
+                        tile_type: if c == '#' {
+                            TileType::Wall
+                        } else {
+                            TileType::Grass
+                        },
+                        content: match c {
+                            'c' => TASK.collect_content(3),
+                            'b' => TASK.deposit_content(5),
+                            _ => Content::None,
+                        },
+                        elevation: 0,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn walled_off_targets() {
+        let stats = MapStats::new(&world(&["..#..", ".c#c.", ".b#b."]), (0, 0));
+        assert_eq!(stats.size, (3, 5));
+        assert_eq!(stats.walkable, 0.8);
+        assert_eq!(stats.danger, 3);
+        assert_eq!((stats.coins, stats.coin_amount, stats.banks), (2, 6, 2));
+        assert_eq!((stats.reachable_coins, stats.reachable_banks), (1, 1));
+        assert!(stats.is_valid());
+    }
+
+    #[test]
+    fn contents_block_the_way() {
+        // the bank is only reachable walking over the coins
+        let stats = MapStats::new(&world(&[".c.", "cc.", "..b"]), (0, 0));
+        assert_eq!((stats.reachable_coins, stats.reachable_banks), (2, 0));
+        assert!(!stats.is_valid());
+    }
+
+    #[test]
+    fn spawn_outside_the_map() {
+        let stats = MapStats::new(&world(&[".cb"]), (1, 0));
+        assert_eq!((stats.reachable_coins, stats.reachable_banks), (0, 0));
+        assert!(!stats.is_valid());
+    }
+}
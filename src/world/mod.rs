@@ -0,0 +1,105 @@
+pub mod cache;
+pub mod generator;
+pub mod map;
+
+use crate::utils::consts::{CACHE_WORLDS, MAP_BASE};
+use cache::WorldCache;
+use generator::ProceduralGenerator;
+use map::MapFile;
+use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
+use robotics_lib::world::tile::{Content, Tile};
+use robotics_lib::world::world_generator::Generator;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use worldgen_unwrap::public::WorldgeneratorUnwrap;
+
+// world, spawn, conditions, max score and score table returned by a generator
+pub type GeneratedWorld = (
+    Vec<Vec<Tile>>,
+    (usize, usize),
+    EnvironmentalConditions,
+    f32,
+    Option<HashMap<Content, f32>>,
+);
+
+/// # World source
+///
+/// Generator of the worlds of a `GymEnv`
+///
+/// - `Unwrap`: `.bin` map made with the worldgen_unwrap editor
+/// - `Map`: `.json` map made by `init generate`
+/// - `Procedural`: new world at every generation, randomized for domain randomization
+/// - `Cached`: in-memory copy of a map, generated only once
+pub enum WorldSource {
+    Unwrap(WorldgeneratorUnwrap),
+    Map(MapFile),
+    Procedural(ProceduralGenerator),
+    Cached(WorldCache),
+}
+
+impl WorldSource {
+    // map file, the format is chosen by the extension, kept in memory if CACHE_WORLDS is set
+    pub fn load(path: &Path) -> Self {
+        let source = Self::open(path);
+        if CACHE_WORLDS {
+            source.cached()
+        } else {
+            source
+        }
+    }
+
+    // map file read again at every generation
+    pub fn open(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => WorldSource::Map(MapFile::load(path)),
+            _ => WorldSource::Unwrap(WorldgeneratorUnwrap::init(false, Some(path.to_path_buf()))),
+        }
+    }
+
+    // keep the map in memory, the procedural worlds are new at every generation so they aren't cached
+    pub fn cached(self) -> Self {
+        match self {
+            WorldSource::Unwrap(mut generator) => {
+                WorldSource::Cached(WorldCache::new(&mut generator))
+            }
+            WorldSource::Map(mut map) => WorldSource::Cached(WorldCache::new(&mut map)),
+            source => source,
+        }
+    }
+
+    // map of the maps folder
+    pub fn map(name: &str) -> Self {
+        Self::load(&PathBuf::from(MAP_BASE).join(name))
+    }
+}
+
+impl WorldSource {
+    // world of the last generation, the tiles the episode started from
+    pub fn snapshot(&mut self) -> Vec<Vec<Tile>> {
+        match self {
+            WorldSource::Unwrap(generator) => generator.gen().0,
+            WorldSource::Map(map) => map.world.clone(),
+            WorldSource::Procedural(generator) => generator.last_world().clone(),
+            WorldSource::Cached(cache) => cache.tiles().clone(),
+        }
+    }
+}
+
+impl Generator for WorldSource {
+    fn gen(
+        &mut self,
+    ) -> (
+        Vec<Vec<Tile>>,
+        (usize, usize),
+        EnvironmentalConditions,
+        f32,
+        Option<HashMap<Content, f32>>,
+    ) {
+        match self {
+            WorldSource::Unwrap(generator) => generator.gen(),
+            WorldSource::Map(map) => map.gen(),
+            WorldSource::Procedural(generator) => generator.gen(),
+            WorldSource::Cached(cache) => cache.gen(),
+        }
+    }
+}